@@ -12,12 +12,12 @@ def main():
             "dX1 = ( 0.05 * X1 ) * dt + ( 0.2 * X1 ) * dW1 + ( 0.5 ) * dN1(X0)",
             "X2 = max(X1 - 100.0, 0.0)",
         ],
-        time_steps=list(np.arange(0.0, 10.0, 0.01)),
+        time_steps=np.arange(0.0, 10.0, 0.01),
         scenarios=10_000,
         initial_values=initial_values,
         rng_method="pseudo",
         scheme="runge-kutta",
-    )
+    ).paths()
     print(df)
     for i in range(0, len(initial_values)):
         fig = px.line(
@@ -13,12 +13,12 @@ def main():
         processes_equations=[
             f"dX1 = ( {mu} * X1 ) * dt + ( {sigma} * X1) * dW1",
         ],
-        time_steps=list(np.arange(0.0, 10.0, 0.1)),
+        time_steps=np.arange(0.0, 10.0, 0.1),
         scenarios=10000,
         initial_values={"X1": start_value},
         rng_method="pseudo",
         scheme="runge-kutta",
-    )
+    ).paths()
     print(df)
     fig = px.line(
         df,
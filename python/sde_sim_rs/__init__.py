@@ -1,3 +1,3 @@
-from .sde_sim_rs import simulate
+from .sde_sim_rs import SimulationResult, simulate
 
-__all__ = ["simulate"]
+__all__ = ["SimulationResult", "simulate"]
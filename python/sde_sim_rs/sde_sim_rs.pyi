@@ -1,16 +1,136 @@
-from collections.abc import Mapping, Sequence
+from collections.abc import Iterable, Mapping, Sequence
 from typing import Literal
 
 import polars as pl
 
+class SimulationResult:
+    """
+    The outcome of a call to `simulate`, holding every scenario in memory.
+    """
+
+    def paths(self, wide: bool = False) -> pl.DataFrame:
+        """
+        Returns the simulated paths as a long/tidy DataFrame with columns
+        `scenario`, `time`, `process_name` and `value` (plus `is_integer` when
+        the system has counting processes). With `wide=True` there is one
+        column per process instead, Int64 for counting processes.
+        """
+        ...
+
+    def statistics(self, quantiles: Sequence[float] = (0.05, 0.5, 0.95)) -> pl.DataFrame:
+        """
+        Returns cross-scenario statistics for every `(time, process)` pair:
+        `mean`, `std`, `min`, `max` and one `q{level}` column per quantile.
+
+        Raises:
+            ValueError: If a quantile level lies outside [0, 1].
+        """
+        ...
+
+    def histogram(
+        self,
+        process: str,
+        times: Sequence[float],
+        bins: int | None = None,
+        range: tuple[float, float] | None = None,
+    ) -> pl.DataFrame:
+        """
+        Returns the cross-scenario histogram of `process` at each of `times`
+        with columns `time`, `bin_left`, `bin_right`, `count`, `density`,
+        `outside` (values beyond `range`) and `skipped` (non-finite values).
+
+        With `range`, `bins` equal-width bins (default 50) cover it; otherwise
+        Freedman-Diaconis bins are shared by all selected times.
+
+        Raises:
+            ValueError: If `bins` is given without `range`, or a time or process
+                is unknown.
+        """
+        ...
+
+    def kde(
+        self,
+        process: str,
+        times: Sequence[float],
+        bandwidth: float | None = None,
+        grid_points: int = 512,
+    ) -> pl.DataFrame:
+        """
+        Returns a Gaussian kernel density estimate of `process` at each of
+        `times` on a grid shared by all selected times, with columns `time`,
+        `x`, `density`, `bandwidth` and `skipped` (non-finite values). The
+        bandwidth defaults to Silverman's rule.
+
+        Raises:
+            ValueError: If a time or process is unknown, or the bandwidth is not
+                positive.
+        """
+        ...
+
+    def var(
+        self,
+        exposure: str | Mapping[str, float],
+        alpha: float,
+        times: Sequence[float],
+    ) -> pl.DataFrame:
+        """
+        Returns the Value-at-Risk at confidence `alpha` (e.g. 0.99) of a
+        process, or of a weighted combination such as `{"A": 0.6, "B": 0.4}`,
+        read as a profit: minus its `1 - alpha` quantile. Columns are `time`,
+        `alpha`, `var`, `quantile` and `excluded` (scenarios skipped for
+        non-finite values). Importance sampling weights are applied.
+
+        Raises:
+            ValueError: If `alpha` lies outside (0, 1), or a time or process is
+                unknown.
+        """
+        ...
+
+    def cvar(
+        self,
+        exposure: str | Mapping[str, float],
+        alpha: float,
+        times: Sequence[float],
+    ) -> pl.DataFrame:
+        """
+        Returns the expected shortfall at confidence `alpha`, minus the mean of
+        the worst `1 - alpha` of outcomes, with columns `time`, `alpha`, `var`,
+        `cvar` and `excluded`. See `var`.
+        """
+        ...
+
+    def shortfall_probability(
+        self,
+        exposure: str | Mapping[str, float],
+        threshold: float,
+        times: Sequence[float],
+    ) -> pl.DataFrame:
+        """
+        Returns the probability of falling strictly below `threshold`, with
+        columns `time`, `threshold`, `probability` and `excluded`. See `var`.
+        """
+        ...
+
+    def increments(self) -> pl.DataFrame:
+        """
+        Returns the sampled stochastic increments with columns `scenario`,
+        `time` (start of the step), `increment_name` and `value`.
+
+        Raises:
+            ValueError: If the simulation was run without `record_increments=True`.
+        """
+        ...
+
 def simulate(
     processes_equations: Sequence[str],
-    time_steps: Sequence[float],
+    time_steps: Iterable[float],
     scenarios: int,
     initial_values: Mapping[str, float],
-    rng_method: Literal["pseudo", "sobol"] = "pseudo",
-    scheme: Literal["euler", "runge-kutta"] = "euler",
-) -> pl.DataFrame:
+    rng_method: Literal["pseudo", "philox", "sobol"] = "pseudo",
+    scheme: Literal["euler", "exp-euler", "milstein", "runge-kutta"] = "euler",
+    record_increments: bool = False,
+    linear_drift: Mapping[str, float] | None = None,
+) -> SimulationResult:
     """
     Simulates stochastic differential equations (SDEs) using the specified methods.
 
@@ -26,9 +146,12 @@ def simulate(
             `dX = (0.5 * X) * dt + (0.2 * X) * dW1`.
             Supported incrementors are `dt` (for the drift term) and `dW` (for
             Wiener processes, e.g., `dW1`, `dW2`).
+            Coefficients may switch form with comparisons, which evaluate to
+            1 or 0, or with `if(cond, a, b)`, e.g.
+            `(if(X < 1.0, 0.3, 0.15) * X) * dW1` or `(0.05 * (t < 5.0)) * dt`.
 
-        time_steps: A sequence of time points at which to calculate the process
-            values. Must be in increasing order.
+        time_steps: A list or numpy array of time points at which to calculate
+            the process values. Must be strictly increasing.
 
         scenarios: The number of simulation paths to generate.
 
@@ -36,22 +159,37 @@ def simulate(
             initial numerical values.
 
         rng_method: The random number generation method to use. Can be **"pseudo"** for
-            pseudorandom numbers or **"sobol"** for Sobol sequences (quasi-random).
+            pseudorandom numbers, **"philox"** for counter-based pseudorandom numbers
+            or **"sobol"** for Sobol sequences (quasi-random).
             Defaults to "pseudo".
 
         scheme: The numerical integration scheme to use. Can be **"euler"** for the
-            Euler-Maruyama method or **"runge-kutta"** for a higher-order Runge-Kutta
-            method. Defaults to "euler".
+            Euler-Maruyama method, **"exp-euler"** for the split-step exponential
+            Euler method (see `linear_drift`), **"milstein"** for the Milstein method or
+            **"runge-kutta"** for a higher-order Runge-Kutta method. Defaults to "euler".
+
+        record_increments: Whether to keep every sampled stochastic increment so
+            that it can be retrieved with `SimulationResult.increments()`.
+            Defaults to False.
+
+        linear_drift: Coefficient `a` of the linear part `a * X` of the drift, by
+            process name. Under "exp-euler" that part is solved exactly, keeping
+            stiff drifts such as `(-200 * (X - 1)) * dt` stable for large steps.
+            Processes without one step with Euler. Defaults to None.
 
     Returns:
-        A Polars DataFrame containing the simulated values. The DataFrame is
-        "long"/tidy: every row represents a single `(scenario, time, process)`
-        triple and the associated value.  In other words, the `scenario`
-        dimension has already been appended, which makes it easy to group or
-        aggregate across paths using standard Polars operations.
+        A `SimulationResult`. Its `paths()` method returns a Polars DataFrame
+        that is "long"/tidy: every row represents a single
+        `(scenario, time, process)` triple and the associated value.  In other
+        words, the `scenario` dimension has already been appended, which makes
+        it easy to group or aggregate across paths using standard Polars
+        operations.
+
+        The GIL is released while the simulation runs.
 
     Raises:
-        ValueError: If the process equations are malformed or if initial values
-            are missing for any process.
+        ValueError: If the process equations are malformed, if initial values
+            are missing for any process, or if `time_steps` is not strictly
+            increasing.
     """
     ...
@@ -0,0 +1,224 @@
+use crate::filtration::Filtration;
+use crate::math::stats;
+use ordered_float::OrderedFloat;
+use polars::prelude::*;
+
+/// Upper bound on the number of bins chosen automatically.
+const MAX_AUTO_BINS: usize = 10_000;
+
+/// Bin layout of [`Filtration::histogram`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinSpec {
+    /// `n` equal-width bins over `[min, max]`; values outside are counted in
+    /// the `outside` column.
+    Fixed { min: f64, max: f64, n: usize },
+    /// Freedman-Diaconis width over the values of all selected times, so every
+    /// time shares the same bins.
+    Auto,
+}
+
+/// Finite values of every selected time plus the number of non-finite ones.
+type CrossSections = Vec<(f64, Vec<f64>, usize)>;
+
+impl Filtration {
+    /// Sorted finite cross-sections of `process` at `times`.
+    fn finite_cross_sections(&self, process: &str, times: &[f64]) -> PolarsResult<CrossSections> {
+        let Some(&p_idx) = self.get_process_idx(process) else {
+            polars_bail!(ComputeError: "unknown process '{}'", process);
+        };
+        times
+            .iter()
+            .map(|&t| {
+                let Some(&t_idx) = self.get_time_idx(OrderedFloat(t)) else {
+                    polars_bail!(ComputeError: "time {} is not part of the filtration", t);
+                };
+                let mut values: Vec<f64> = (0..self.num_scenarios())
+                    .map(|s_idx| self.get(s_idx, t_idx, p_idx))
+                    .filter(|v| v.is_finite())
+                    .collect();
+                values.sort_by(|a, b| a.total_cmp(b));
+                let skipped = self.num_scenarios() - values.len();
+                Ok((t, values, skipped))
+            })
+            .collect()
+    }
+
+    /// Histogram of `process` across scenarios at each of `times`, with columns
+    /// `time`, `bin_left`, `bin_right`, `count`, `density`, `outside` and
+    /// `skipped`. Non-finite values are skipped; `density` is normalized by the
+    /// number of finite values, including those outside the bins.
+    pub fn histogram(
+        &self,
+        process: &str,
+        times: &[f64],
+        bins: BinSpec,
+    ) -> PolarsResult<DataFrame> {
+        let sections = self.finite_cross_sections(process, times)?;
+        let (min, max, n) = match bins {
+            BinSpec::Fixed { min, max, n } => {
+                if n == 0 || min >= max || !min.is_finite() || !max.is_finite() {
+                    polars_bail!(ComputeError: "fixed bins need n > 0 and min < max");
+                }
+                (min, max, n)
+            }
+            BinSpec::Auto => auto_bins(&sections),
+        };
+        let width = (max - min) / n as f64;
+
+        let num_rows = sections.len() * n;
+        let mut time_col = Vec::with_capacity(num_rows);
+        let mut lefts = Vec::with_capacity(num_rows);
+        let mut rights = Vec::with_capacity(num_rows);
+        let mut counts: Vec<u64> = Vec::with_capacity(num_rows);
+        let mut densities = Vec::with_capacity(num_rows);
+        let mut outside_col: Vec<u64> = Vec::with_capacity(num_rows);
+        let mut skipped_col: Vec<u64> = Vec::with_capacity(num_rows);
+        for (t, values, skipped) in &sections {
+            let mut bin_counts = vec![0u64; n];
+            let mut outside = 0u64;
+            for &v in values {
+                if v < min || v > max {
+                    outside += 1;
+                } else {
+                    // the last bin is closed on the right
+                    let b = (((v - min) / width) as usize).min(n - 1);
+                    bin_counts[b] += 1;
+                }
+            }
+            for (b, count) in bin_counts.into_iter().enumerate() {
+                time_col.push(*t);
+                lefts.push(min + b as f64 * width);
+                rights.push(min + (b + 1) as f64 * width);
+                counts.push(count);
+                densities.push(count as f64 / (values.len() as f64 * width));
+                outside_col.push(outside);
+                skipped_col.push(*skipped as u64);
+            }
+        }
+        DataFrame::new(vec![
+            Column::new("time".into(), time_col),
+            Column::new("bin_left".into(), lefts),
+            Column::new("bin_right".into(), rights),
+            Column::new("count".into(), counts),
+            Column::new("density".into(), densities),
+            Column::new("outside".into(), outside_col),
+            Column::new("skipped".into(), skipped_col),
+        ])
+    }
+
+    /// Gaussian kernel density estimate of `process` across scenarios at each
+    /// of `times`, evaluated on `grid_points` points spanning all selected
+    /// times, with columns `time`, `x`, `density`, `bandwidth` and `skipped`.
+    /// Non-finite values are skipped. Without `bandwidth`, Silverman's rule is
+    /// applied per time.
+    pub fn kde(
+        &self,
+        process: &str,
+        times: &[f64],
+        bandwidth: Option<f64>,
+        grid_points: usize,
+    ) -> PolarsResult<DataFrame> {
+        if grid_points < 2 {
+            polars_bail!(ComputeError: "the KDE grid needs at least 2 points");
+        }
+        if let Some(h) = bandwidth
+            && !(h > 0.0 && h.is_finite())
+        {
+            polars_bail!(ComputeError: "bandwidth must be positive, got {}", h);
+        }
+        let sections = self.finite_cross_sections(process, times)?;
+        let bandwidths: Vec<f64> = sections
+            .iter()
+            .map(|(_, values, _)| bandwidth.unwrap_or_else(|| silverman_bandwidth(values)))
+            .collect();
+        let (lo, hi) = sections
+            .iter()
+            .zip(&bandwidths)
+            .filter_map(|((_, values, _), h)| {
+                Some((values.first()? - 3.0 * h, values.last()? + 3.0 * h))
+            })
+            .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), (a, b)| {
+                (lo.min(a), hi.max(b))
+            });
+        let grid: Vec<f64> = if lo <= hi {
+            (0..grid_points)
+                .map(|i| lo + (hi - lo) * i as f64 / (grid_points - 1) as f64)
+                .collect()
+        } else {
+            vec![f64::NAN; grid_points]
+        };
+
+        let num_rows = sections.len() * grid_points;
+        let mut time_col = Vec::with_capacity(num_rows);
+        let mut xs = Vec::with_capacity(num_rows);
+        let mut densities = Vec::with_capacity(num_rows);
+        let mut bandwidth_col = Vec::with_capacity(num_rows);
+        let mut skipped_col: Vec<u64> = Vec::with_capacity(num_rows);
+        for ((t, values, skipped), &h) in sections.iter().zip(&bandwidths) {
+            let norm = 1.0 / (values.len() as f64 * h * (2.0 * std::f64::consts::PI).sqrt());
+            for &x in &grid {
+                // values beyond 8 bandwidths contribute below 1e-14
+                let from = values.partition_point(|v| *v < x - 8.0 * h);
+                let to = values.partition_point(|v| *v <= x + 8.0 * h);
+                let sum: f64 = values[from..to]
+                    .iter()
+                    .map(|v| (-0.5 * ((x - v) / h).powi(2)).exp())
+                    .sum();
+                time_col.push(*t);
+                xs.push(x);
+                densities.push(sum * norm);
+                bandwidth_col.push(h);
+                skipped_col.push(*skipped as u64);
+            }
+        }
+        DataFrame::new(vec![
+            Column::new("time".into(), time_col),
+            Column::new("x".into(), xs),
+            Column::new("density".into(), densities),
+            Column::new("bandwidth".into(), bandwidth_col),
+            Column::new("skipped".into(), skipped_col),
+        ])
+    }
+}
+
+/// Range and bin count from the Freedman-Diaconis rule over the pooled values,
+/// with the sample size of an average time. Falls back to Sturges' rule when
+/// the interquartile range vanishes.
+fn auto_bins(sections: &CrossSections) -> (f64, f64, usize) {
+    let mut pooled: Vec<f64> = sections
+        .iter()
+        .flat_map(|(_, v, _)| v.iter().copied())
+        .collect();
+    if pooled.is_empty() {
+        return (0.0, 1.0, 1);
+    }
+    pooled.sort_by(|a, b| a.total_cmp(b));
+    let (min, max) = (pooled[0], pooled[pooled.len() - 1]);
+    if min == max {
+        return (min - 0.5, max + 0.5, 1);
+    }
+    let n = (pooled.len() as f64 / sections.len() as f64).max(1.0);
+    let iqr = stats::quantile_sorted(&pooled, 0.75) - stats::quantile_sorted(&pooled, 0.25);
+    let bins = if iqr > 0.0 {
+        let width = 2.0 * iqr / n.cbrt();
+        ((max - min) / width).ceil() as usize
+    } else {
+        n.log2().ceil() as usize + 1
+    };
+    (min, max, bins.clamp(1, MAX_AUTO_BINS))
+}
+
+/// Silverman's rule of thumb `0.9 min(sigma, IQR / 1.34) n^(-1/5)` for sorted
+/// values, falling back to the standard deviation when the IQR vanishes and to
+/// a small multiple of the magnitude for constant samples.
+fn silverman_bandwidth(sorted: &[f64]) -> f64 {
+    let n = sorted.len() as f64;
+    let sigma = stats::std_dev(sorted);
+    let iqr = stats::quantile_sorted(sorted, 0.75) - stats::quantile_sorted(sorted, 0.25);
+    let spread = match (sigma > 0.0, iqr > 0.0) {
+        (true, true) => sigma.min(iqr / 1.34),
+        (true, false) => sigma,
+        _ => 1e-3 * sorted.first().map_or(1.0, |v| v.abs().max(1.0)),
+    };
+    0.9 * spread * n.max(1.0).powf(-0.2)
+}
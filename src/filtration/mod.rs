@@ -0,0 +1,849 @@
+#[cfg(feature = "dataframe")]
+pub mod density;
+pub mod regression;
+#[cfg(feature = "dataframe")]
+pub mod statistics;
+
+use crate::proc::{ProcessUniverse, ValueKind};
+use ordered_float::OrderedFloat;
+#[cfg(feature = "dataframe")]
+use polars::prelude::*;
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+/// Index of the last grid time at or before `time`.
+///
+/// A time within a relative `1e-9` of a grid point counts as that point, so
+/// `t - lag` computed in floating point still lands on the intended step. Times
+/// before the first grid point resolve to index 0, i.e. the initial value is
+/// used for any pre-history.
+pub(crate) fn index_at_or_before(times: &[OrderedFloat<f64>], time: f64) -> usize {
+    let tolerance = 1e-9 * time.abs().max(1.0);
+    times
+        .partition_point(|t| t.0 <= time + tolerance)
+        .saturating_sub(1)
+}
+
+/// Updates a cached value in place, only allocating the key on first insert.
+#[inline]
+fn set_cache_value(values: &mut BTreeMap<String, f64>, name: &str, val: f64) {
+    match values.get_mut(name) {
+        Some(v) => *v = val,
+        None => {
+            values.insert(name.to_string(), val);
+        }
+    }
+}
+
+pub struct ScenarioFiltrationCache {
+    pub time: OrderedFloat<f64>,
+    pub values: BTreeMap<String, f64>,
+}
+
+pub struct ScenarioFiltration {
+    pub scenario: i32,
+    pub times: Vec<OrderedFloat<f64>>,
+    pub process_universe: ProcessUniverse,
+    raw_values: Vec<f64>,
+    time_registry: HashMap<OrderedFloat<f64>, usize>,
+    pub cache: ScenarioFiltrationCache,
+    /// Sampled stochastic increments, `[time step][stochastic index]`, when
+    /// recording is enabled.
+    increments: Option<Vec<f64>>,
+    /// Delayed references `(variable, process index, lag)` filled on refresh.
+    lags: Vec<(String, usize, f64)>,
+    /// Drift added to each stochastic increment over the current step under
+    /// importance sampling; empty when importance sampling is off.
+    increment_shifts: Vec<f64>,
+    /// Log of the accumulated likelihood ratio dP/dQ.
+    log_weight: f64,
+    /// Moment-matched Wiener increments of step `matched_step`, by stochastic
+    /// index, replacing the drawn ones.
+    matched_increments: Vec<f64>,
+    matched_step: Option<usize>,
+}
+
+impl ScenarioFiltration {
+    pub fn new(
+        scenario: i32,
+        process_universe: ProcessUniverse,
+        times: Vec<OrderedFloat<f64>>,
+        initial_values: HashMap<String, f64>,
+    ) -> Self {
+        let raw_values = vec![0.0; times.len() * process_universe.processes.len()];
+        let time_registry = times.iter().enumerate().map(|(i, t)| (*t, i)).collect();
+        let lags = process_universe
+            .lags()
+            .into_iter()
+            .filter_map(|l| {
+                let p_idx = *process_universe.process_registry.get(&l.process)?;
+                Some((l.var_name, p_idx, l.lag))
+            })
+            .collect();
+        let value_cache = ScenarioFiltrationCache {
+            time: times[0],
+            values: BTreeMap::new(),
+        };
+        let mut scenario_filtration = ScenarioFiltration {
+            scenario,
+            process_universe,
+            times,
+            raw_values,
+            time_registry,
+            cache: value_cache,
+            increments: None,
+            lags,
+            increment_shifts: Vec::new(),
+            log_weight: 0.0,
+            matched_increments: Vec::new(),
+            matched_step: None,
+        };
+        for (process_name, val) in initial_values.into_iter() {
+            if let Some(process_idx) = scenario_filtration
+                .process_universe
+                .process_registry
+                .get(&process_name)
+            {
+                scenario_filtration.set(0, *process_idx, val);
+            }
+        }
+        scenario_filtration.refresh_cache(scenario_filtration.times[0]);
+        scenario_filtration
+    }
+
+    #[inline]
+    pub fn get(&self, time_idx: usize, process_idx: usize) -> f64 {
+        self.raw_values[time_idx * self.process_universe.processes.len() + process_idx]
+    }
+
+    #[inline]
+    pub fn set(&mut self, time_idx: usize, process_idx: usize, val: f64) {
+        let idx = time_idx * self.process_universe.processes.len() + process_idx;
+        self.raw_values[idx] = val;
+    }
+
+    pub fn get_time_idx(&self, time: OrderedFloat<f64>) -> Option<&usize> {
+        self.time_registry.get(&time)
+    }
+
+    pub fn refresh_cache(&mut self, time: OrderedFloat<f64>) {
+        self.cache.time = time;
+        self.set_context_value("t", time.into_inner());
+        let t_idx = self.get_time_idx(time).copied().unwrap_or(0);
+        for p_idx in 0..self.process_universe.processes.len() {
+            let val = self.get(t_idx, p_idx);
+            set_cache_value(
+                &mut self.cache.values,
+                self.process_universe.processes[p_idx].name(),
+                val,
+            );
+        }
+        for (var_name, p_idx, lag) in self.lags.iter() {
+            let lag_idx = index_at_or_before(&self.times, time.0 - lag);
+            let val = self.get(lag_idx, *p_idx);
+            set_cache_value(&mut self.cache.values, var_name, val);
+        }
+    }
+
+    /// Value of process `process_idx` at the last grid time at or before `time`.
+    pub fn value_at_or_before(&self, time: f64, process_idx: usize) -> f64 {
+        self.get(index_at_or_before(&self.times, time), process_idx)
+    }
+
+    /// Starts keeping every sampled stochastic increment of this scenario.
+    pub fn enable_increment_recording(&mut self) {
+        let num_increments = self.process_universe.stochastic_registry.len();
+        self.increments = Some(vec![0.0; (self.times.len() - 1) * num_increments]);
+    }
+
+    /// Stores the value drawn for stochastic increment `increment_idx` over the
+    /// step starting at `time_idx`; a no-op unless recording is enabled.
+    #[inline]
+    pub fn record_increment(&mut self, time_idx: usize, increment_idx: usize, val: f64) {
+        if let Some(increments) = self.increments.as_mut() {
+            let num_increments = self.process_universe.stochastic_registry.len();
+            increments[time_idx * num_increments + increment_idx] = val;
+        }
+    }
+
+    /// Starts tracking drift shifts and the likelihood ratio of this scenario.
+    pub fn enable_importance_sampling(&mut self) {
+        self.increment_shifts = vec![0.0; self.process_universe.stochastic_registry.len()];
+    }
+
+    /// Sets the drift added to stochastic increment `increment_idx` over the
+    /// current step.
+    pub fn set_increment_shift(&mut self, increment_idx: usize, shift: f64) {
+        self.increment_shifts[increment_idx] = shift;
+    }
+
+    /// Applies the current drift shift, if any, to a sampled increment.
+    #[inline]
+    pub fn shift_increment(&self, increment_idx: usize, val: f64) -> f64 {
+        match self.increment_shifts.get(increment_idx) {
+            Some(shift) => val + shift,
+            None => val,
+        }
+    }
+
+    /// Replaces the Wiener increments of step `time_idx` with `increments`,
+    /// given as `(stochastic index, value)`.
+    pub fn set_matched_increments(&mut self, time_idx: usize, increments: &[(usize, f64)]) {
+        let num_increments = self.process_universe.stochastic_registry.len();
+        self.matched_increments.resize(num_increments, 0.0);
+        for (idx, val) in increments {
+            self.matched_increments[*idx] = *val;
+        }
+        self.matched_step = Some(time_idx);
+    }
+
+    /// Moment-matched value of Wiener increment `increment_idx` over step
+    /// `time_idx`, if one was set.
+    #[inline]
+    pub fn matched_increment(&self, time_idx: usize, increment_idx: usize) -> Option<f64> {
+        (self.matched_step == Some(time_idx)).then(|| self.matched_increments[increment_idx])
+    }
+
+    pub fn add_log_weight(&mut self, val: f64) {
+        self.log_weight += val;
+    }
+
+    /// Likelihood ratio of the scenario, if importance sampling is enabled.
+    pub fn weight(&self) -> Option<f64> {
+        (!self.increment_shifts.is_empty()).then(|| self.log_weight.exp())
+    }
+
+    /// Makes an additional named value (e.g. a mean-field aggregate) available to
+    /// expressions. It persists until overwritten.
+    pub fn set_context_value(&mut self, name: &str, val: f64) {
+        set_cache_value(&mut self.cache.values, name, val);
+    }
+
+    /// Clears the path and recorded increments and starts over from
+    /// `initial_values`, keeping the buffers and context values.
+    pub fn reset(&mut self, initial_values: &HashMap<String, f64>) {
+        self.raw_values.fill(0.0);
+        if let Some(increments) = self.increments.as_mut() {
+            increments.fill(0.0);
+        }
+        self.increment_shifts.fill(0.0);
+        self.log_weight = 0.0;
+        self.matched_step = None;
+        for (process_name, val) in initial_values.iter() {
+            if let Some(process_idx) = self.process_universe.process_registry.get(process_name) {
+                let process_idx = *process_idx;
+                self.set(0, process_idx, *val);
+            }
+        }
+        self.refresh_cache(self.times[0]);
+    }
+
+    #[cfg(feature = "dataframe")]
+    pub fn to_lazyframe(&self) -> LazyFrame {
+        let num_procs = self.process_universe.processes.len();
+        let num_times = self.times.len();
+
+        // 1. Fixed PlSmallStr by adding .into()
+        // and using StringChunked::from_iter for cleaner collection
+        let process_names: Series = StringChunked::from_iter(
+            self.times
+                .iter()
+                .flat_map(|_| self.process_universe.processes.iter().map(|p| p.name())),
+        )
+        .with_name("process_name".into())
+        .into_series();
+
+        // 2. Fixed Float64Chunked collection
+        // We use Float64Chunked::from_iter and .into() for the name
+        let times: Series = Float64Chunked::from_iter(
+            self.times
+                .iter()
+                .flat_map(|t| std::iter::repeat_n(Some(t.0), num_procs)),
+        )
+        .with_name("time".into())
+        .into_series();
+
+        // 3. Build the DataFrame
+        // Note: The df! macro in 0.51 also expects PlSmallStr for column names
+        // but the macro usually handles string literals via internal conversion.
+        df![
+            "scenario" => [self.scenario].repeat(num_procs * num_times),
+            "time" => times,
+            "process_name" => process_names,
+            "value" => &self.raw_values
+        ]
+        .expect("Failed to create DataFrame")
+        .lazy()
+    }
+}
+
+/// Values of every process over all scenarios of a simulation run.
+///
+/// Values are stored scenario-major, i.e. each scenario's path is a contiguous
+/// block laid out exactly like a [`ScenarioFiltration`].
+pub struct Filtration {
+    pub times: Vec<OrderedFloat<f64>>,
+    pub scenarios: Vec<i32>,
+    pub process_names: Vec<String>,
+    /// Names of the stochastic increments, ordered by their index.
+    pub increment_names: Vec<String>,
+    /// Replication each scenario belongs to, for runs split into replications.
+    pub replications: Option<Vec<usize>>,
+    /// Likelihood ratio of every scenario under importance sampling; weight
+    /// any expectation by these to estimate it under the original measure.
+    pub weights: Option<Vec<f64>>,
+    /// Value kind of every process, in the order of `process_names`.
+    pub value_kinds: Vec<ValueKind>,
+    /// Accepted steps of every scenario of an adaptive run.
+    pub step_counts: Option<Vec<usize>>,
+    raw_values: Vec<f64>,
+    /// Recorded increments, `[scenario][time step][increment]`, if requested.
+    increments: Option<Vec<f64>>,
+    time_registry: HashMap<OrderedFloat<f64>, usize>,
+    process_registry: HashMap<String, usize>,
+    /// Number of leading time points simulated in each scenario; later cells
+    /// hold NaN until the scenario is resumed.
+    filled: Vec<usize>,
+}
+
+impl Filtration {
+    /// Assembles a filtration from per-scenario results that share the same time
+    /// grid and process universe.
+    pub fn from_scenarios(scenario_filtrations: Vec<ScenarioFiltration>) -> Self {
+        let mut filtration = Self::with_layout(&scenario_filtrations.iter().collect::<Vec<_>>());
+        for (s_idx, f) in scenario_filtrations.iter().enumerate() {
+            filtration.store_scenario(s_idx, f);
+        }
+        filtration
+    }
+
+    /// Zero-filled filtration shaped to hold `scenario_filtrations`.
+    pub(crate) fn with_layout(scenario_filtrations: &[&ScenarioFiltration]) -> Self {
+        let times = scenario_filtrations
+            .first()
+            .map(|f| f.times.clone())
+            .unwrap_or_default();
+        let (process_names, increment_names): (Vec<String>, Vec<String>) = scenario_filtrations
+            .first()
+            .map(|f| {
+                let universe = &f.process_universe;
+                let mut increment_names = vec![String::new(); universe.stochastic_registry.len()];
+                for (name, idx) in universe.stochastic_registry.iter() {
+                    increment_names[*idx] = name.clone();
+                }
+                let process_names = universe
+                    .processes
+                    .iter()
+                    .map(|p| p.name().to_string())
+                    .collect();
+                (process_names, increment_names)
+            })
+            .unwrap_or_default();
+        let record_increments = !scenario_filtrations.is_empty()
+            && scenario_filtrations.iter().all(|f| f.increments.is_some());
+        let scenarios = scenario_filtrations.iter().map(|f| f.scenario).collect();
+        let raw_values = vec![0.0; scenario_filtrations.len() * times.len() * process_names.len()];
+        let increments = record_increments.then(|| {
+            vec![0.0; scenario_filtrations.len() * (times.len() - 1) * increment_names.len()]
+        });
+        let weighted = !scenario_filtrations.is_empty()
+            && scenario_filtrations.iter().all(|f| f.weight().is_some());
+        let num_scenarios = scenario_filtrations.len();
+        let mut filtration = Self::new(times, scenarios, process_names, raw_values);
+        filtration.increment_names = increment_names;
+        filtration.increments = increments;
+        filtration.weights = weighted.then(|| vec![1.0; num_scenarios]);
+        filtration
+    }
+
+    /// Copies a scenario's path (and recorded increments) into slot `scenario_idx`.
+    pub(crate) fn store_scenario(&mut self, scenario_idx: usize, f: &ScenarioFiltration) {
+        self.scenarios[scenario_idx] = f.scenario;
+        self.filled[scenario_idx] = self.times.len();
+        let start = self.offset(scenario_idx, 0, 0);
+        self.raw_values[start..start + f.raw_values.len()].copy_from_slice(&f.raw_values);
+        if let (Some(increments), Some(incs)) = (self.increments.as_mut(), f.increments.as_ref()) {
+            let start = scenario_idx * incs.len();
+            increments[start..start + incs.len()].copy_from_slice(incs);
+        }
+        if let (Some(weights), Some(weight)) = (self.weights.as_mut(), f.weight()) {
+            weights[scenario_idx] = weight;
+        }
+    }
+
+    fn new(
+        times: Vec<OrderedFloat<f64>>,
+        scenarios: Vec<i32>,
+        process_names: Vec<String>,
+        raw_values: Vec<f64>,
+    ) -> Self {
+        let time_registry = times.iter().enumerate().map(|(i, t)| (*t, i)).collect();
+        let process_registry = process_names
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (p.clone(), i))
+            .collect();
+        let filled = vec![times.len(); scenarios.len()];
+        let value_kinds = vec![ValueKind::Continuous; process_names.len()];
+        Self {
+            filled,
+            value_kinds,
+            step_counts: None,
+            times,
+            scenarios,
+            process_names,
+            increment_names: Vec::new(),
+            replications: None,
+            weights: None,
+            raw_values,
+            increments: None,
+            time_registry,
+            process_registry,
+        }
+    }
+
+    /// Appends `new_times` to the time axis. Existing paths are kept and the
+    /// new cells are left unsimulated (NaN) until the filtration is resumed,
+    /// see [`crate::sim::resume_with_config`].
+    pub fn extend_times(&mut self, new_times: Vec<OrderedFloat<f64>>) -> Result<(), String> {
+        let mut last = self.times.last().copied();
+        for (i, t) in new_times.iter().enumerate() {
+            if !t.0.is_finite() {
+                return Err(format!("new_times[{}] is not finite", i));
+            }
+            if let Some(prev) = last
+                && *t <= prev
+            {
+                return Err(format!(
+                    "new_times must strictly follow the last time, but new_times[{}] = {} follows {}",
+                    i, t.0, prev.0
+                ));
+            }
+            last = Some(*t);
+        }
+        if new_times.is_empty() {
+            return Ok(());
+        }
+
+        let num_procs = self.process_names.len();
+        let old_len = self.times.len();
+        let new_len = old_len + new_times.len();
+        let mut raw_values = vec![f64::NAN; self.scenarios.len() * new_len * num_procs];
+        for (old, new) in self
+            .raw_values
+            .chunks_exact(old_len * num_procs)
+            .zip(raw_values.chunks_exact_mut(new_len * num_procs))
+        {
+            new[..old.len()].copy_from_slice(old);
+        }
+        self.raw_values = raw_values;
+
+        if let Some(increments) = self.increments.as_mut() {
+            let num_increments = self.increment_names.len();
+            let old_steps = old_len.saturating_sub(1) * num_increments;
+            let new_steps = (new_len - 1) * num_increments;
+            let mut grown = vec![0.0; self.scenarios.len() * new_steps];
+            if old_steps > 0 {
+                for (old, new) in increments
+                    .chunks_exact(old_steps)
+                    .zip(grown.chunks_exact_mut(new_steps))
+                {
+                    new[..old.len()].copy_from_slice(old);
+                }
+            }
+            *increments = grown;
+        }
+
+        self.times.extend(new_times);
+        self.time_registry = self
+            .times
+            .iter()
+            .enumerate()
+            .map(|(i, t)| (*t, i))
+            .collect();
+        Ok(())
+    }
+
+    /// Adds scenarios `new_ids` starting from `initial_values` (processes not
+    /// listed start at 0). Their paths are left unsimulated (NaN) until the
+    /// filtration is resumed; existing scenarios are untouched.
+    pub fn append_scenarios(
+        &mut self,
+        new_ids: Vec<i32>,
+        initial_values: &HashMap<String, f64>,
+    ) -> Result<(), String> {
+        let mut seen: std::collections::HashSet<i32> = self.scenarios.iter().copied().collect();
+        for id in &new_ids {
+            if *id < 0 {
+                return Err(format!("Scenario ids must be non-negative, got {}", id));
+            }
+            if !seen.insert(*id) {
+                return Err(format!("Scenario {} already exists", id));
+            }
+        }
+        if let Some(name) = initial_values
+            .keys()
+            .find(|name| !self.process_registry.contains_key(*name))
+        {
+            return Err(format!("Unknown process '{}' in initial values", name));
+        }
+
+        let num_procs = self.process_names.len();
+        let block = self.times.len() * num_procs;
+        for id in new_ids {
+            let start = self.raw_values.len();
+            self.raw_values.resize(start + block, f64::NAN);
+            for (p_idx, name) in self.process_names.iter().enumerate() {
+                self.raw_values[start + p_idx] = initial_values.get(name).copied().unwrap_or(0.0);
+            }
+            if let Some(increments) = self.increments.as_mut() {
+                let steps = self.times.len().saturating_sub(1) * self.increment_names.len();
+                increments.resize(increments.len() + steps, 0.0);
+            }
+            if let Some(weights) = self.weights.as_mut() {
+                weights.push(1.0);
+            }
+            self.scenarios.push(id);
+            self.filled.push(1);
+        }
+        // replication membership of the new scenarios is set when they are simulated
+        self.replications = None;
+        self.step_counts = None;
+        Ok(())
+    }
+
+    /// Whether the sampled increments were recorded.
+    pub fn has_increments(&self) -> bool {
+        self.increments.is_some()
+    }
+
+    /// New filtration holding only the scenarios at `scenario_indices`, in
+    /// that order, with their increments, weights and replications.
+    pub fn select_scenarios(&self, scenario_indices: &[usize]) -> Result<Filtration, String> {
+        if let Some(idx) = scenario_indices
+            .iter()
+            .find(|i| **i >= self.num_scenarios())
+        {
+            return Err(format!("Scenario index {} is out of range", idx));
+        }
+        let block = self.times.len() * self.process_names.len();
+        let mut raw_values = Vec::with_capacity(scenario_indices.len() * block);
+        for &s_idx in scenario_indices {
+            raw_values.extend_from_slice(&self.raw_values[s_idx * block..(s_idx + 1) * block]);
+        }
+        let scenarios = scenario_indices
+            .iter()
+            .map(|&i| self.scenarios[i])
+            .collect();
+        let mut selected = Self::new(
+            self.times.clone(),
+            scenarios,
+            self.process_names.clone(),
+            raw_values,
+        );
+        selected.increment_names = self.increment_names.clone();
+        selected.increments = self.increments.as_ref().map(|increments| {
+            let block = self.times.len().saturating_sub(1) * self.increment_names.len();
+            scenario_indices
+                .iter()
+                .flat_map(|&i| increments[i * block..(i + 1) * block].iter().copied())
+                .collect()
+        });
+        selected.weights = self
+            .weights
+            .as_ref()
+            .map(|w| scenario_indices.iter().map(|&i| w[i]).collect());
+        selected.replications = self
+            .replications
+            .as_ref()
+            .map(|r| scenario_indices.iter().map(|&i| r[i]).collect());
+        selected.filled = scenario_indices.iter().map(|&i| self.filled[i]).collect();
+        selected.value_kinds = self.value_kinds.clone();
+        selected.step_counts = self
+            .step_counts
+            .as_ref()
+            .map(|c| scenario_indices.iter().map(|&i| c[i]).collect());
+        Ok(selected)
+    }
+
+    /// Number of leading time points already simulated in scenario `scenario_idx`.
+    pub fn simulated_len(&self, scenario_idx: usize) -> usize {
+        self.filled[scenario_idx]
+    }
+
+    /// Loads the simulated part of scenario `scenario_idx` (path, recorded
+    /// increments and likelihood ratio) into `f` to continue it.
+    pub(crate) fn load_scenario(&self, scenario_idx: usize, f: &mut ScenarioFiltration) {
+        let filled = self.filled[scenario_idx];
+        let num_procs = self.process_names.len();
+        let start = self.offset(scenario_idx, 0, 0);
+        f.raw_values[..filled * num_procs]
+            .copy_from_slice(&self.raw_values[start..start + filled * num_procs]);
+        if let (Some(increments), Some(incs)) = (self.increments.as_ref(), f.increments.as_mut()) {
+            let steps = (filled - 1) * self.increment_names.len();
+            let start = scenario_idx * incs.len();
+            incs[..steps].copy_from_slice(&increments[start..start + steps]);
+        }
+        if let Some(weights) = &self.weights {
+            f.log_weight = weights[scenario_idx].ln();
+        }
+        f.refresh_cache(f.times[filled - 1]);
+    }
+
+    #[inline]
+    fn offset(&self, scenario_idx: usize, time_idx: usize, process_idx: usize) -> usize {
+        let num_procs = self.process_names.len();
+        (scenario_idx * self.times.len() + time_idx) * num_procs + process_idx
+    }
+
+    #[inline]
+    pub fn get(&self, scenario_idx: usize, time_idx: usize, process_idx: usize) -> f64 {
+        self.raw_values[self.offset(scenario_idx, time_idx, process_idx)]
+    }
+
+    #[inline]
+    pub fn set(&mut self, scenario_idx: usize, time_idx: usize, process_idx: usize, val: f64) {
+        let idx = self.offset(scenario_idx, time_idx, process_idx);
+        self.raw_values[idx] = val;
+    }
+
+    /// Value of an integer process as an integer; `None` for continuous
+    /// processes and unsimulated cells.
+    pub fn value_as_int(
+        &self,
+        scenario_idx: usize,
+        time_idx: usize,
+        process_idx: usize,
+    ) -> Option<i64> {
+        let val = self.get(scenario_idx, time_idx, process_idx);
+        (self.value_kinds[process_idx] == ValueKind::Integer && val.is_finite())
+            .then(|| val.round() as i64)
+    }
+
+    pub fn get_time_idx(&self, time: OrderedFloat<f64>) -> Option<&usize> {
+        self.time_registry.get(&time)
+    }
+
+    pub fn get_process_idx(&self, process_name: &str) -> Option<&usize> {
+        self.process_registry.get(process_name)
+    }
+
+    pub fn num_scenarios(&self) -> usize {
+        self.scenarios.len()
+    }
+
+    /// Cross-section of `process_name` at `time`, one value per scenario.
+    pub fn process_values(&self, time: f64, process_name: &str) -> Result<Vec<f64>, String> {
+        let t_idx = *self
+            .get_time_idx(OrderedFloat(time))
+            .ok_or_else(|| format!("Time {} is not part of the filtration", time))?;
+        let p_idx = *self
+            .get_process_idx(process_name)
+            .ok_or_else(|| format!("Unknown process '{}'", process_name))?;
+        Ok((0..self.scenarios.len())
+            .map(|s_idx| self.get(s_idx, t_idx, p_idx))
+            .collect())
+    }
+
+    /// Value of `process_name` in scenario `scenario_idx` at the last grid time
+    /// at or before `time`. Pre-history resolves to the initial value.
+    pub fn value_at_or_before(
+        &self,
+        time: f64,
+        scenario_idx: usize,
+        process_name: &str,
+    ) -> Result<f64, String> {
+        if scenario_idx >= self.scenarios.len() {
+            return Err(format!("Scenario index {} is out of range", scenario_idx));
+        }
+        let p_idx = *self
+            .get_process_idx(process_name)
+            .ok_or_else(|| format!("Unknown process '{}'", process_name))?;
+        let t_idx = index_at_or_before(&self.times, time);
+        Ok(self.get(scenario_idx, t_idx, p_idx))
+    }
+
+    /// Long-format columns `(time, scenario, process name, value)` with one
+    /// row per scenario, time and process, in the row order of
+    /// `to_lazyframe`, for consumers without Polars.
+    pub fn to_vecs(&self) -> (Vec<f64>, Vec<i32>, Vec<String>, Vec<f64>) {
+        let num_procs = self.process_names.len();
+        let rows_per_scenario = num_procs * self.times.len();
+        let times = self
+            .scenarios
+            .iter()
+            .flat_map(|_| {
+                self.times
+                    .iter()
+                    .flat_map(|t| std::iter::repeat_n(t.0, num_procs))
+            })
+            .collect();
+        let scenarios = self
+            .scenarios
+            .iter()
+            .flat_map(|s| std::iter::repeat_n(*s, rows_per_scenario))
+            .collect();
+        let names = (0..self.scenarios.len() * self.times.len())
+            .flat_map(|_| self.process_names.iter().cloned())
+            .collect();
+        (times, scenarios, names, self.raw_values.clone())
+    }
+
+    #[cfg(feature = "dataframe")]
+    pub fn to_lazyframe(&self) -> LazyFrame {
+        let num_procs = self.process_names.len();
+        let num_times = self.times.len();
+        let rows_per_scenario = num_procs * num_times;
+
+        let scenarios: Vec<i32> = self
+            .scenarios
+            .iter()
+            .flat_map(|s| std::iter::repeat_n(*s, rows_per_scenario))
+            .collect();
+        let times: Series = Float64Chunked::from_iter(self.scenarios.iter().flat_map(|_| {
+            self.times
+                .iter()
+                .flat_map(|t| std::iter::repeat_n(Some(t.0), num_procs))
+        }))
+        .with_name("time".into())
+        .into_series();
+        let process_names: Series = StringChunked::from_iter(
+            (0..self.scenarios.len() * num_times)
+                .flat_map(|_| self.process_names.iter().map(|p| p.as_str())),
+        )
+        .with_name("process_name".into())
+        .into_series();
+
+        let mut df = df![
+            "scenario" => scenarios,
+            "time" => times,
+            "process_name" => process_names,
+            "value" => &self.raw_values
+        ]
+        .expect("Failed to create DataFrame");
+        if let Some(weights) = &self.weights {
+            let weights: Vec<f64> = weights
+                .iter()
+                .flat_map(|w| std::iter::repeat_n(*w, rows_per_scenario))
+                .collect();
+            df.with_column(Column::new("weight".into(), weights))
+                .expect("Failed to add weight column");
+        }
+        if self.value_kinds.contains(&ValueKind::Integer) {
+            let is_integer: Vec<bool> = (0..self.scenarios.len() * num_times)
+                .flat_map(|_| self.value_kinds.iter().map(|k| *k == ValueKind::Integer))
+                .collect();
+            df.with_column(Column::new("is_integer".into(), is_integer))
+                .expect("Failed to add is_integer column");
+        }
+        df.lazy()
+    }
+
+    /// Wide-format frame with columns `scenario`, `time` and one column per
+    /// process, `Int64` for integer processes (null where unsimulated) and
+    /// `Float64` otherwise, plus `weight` under importance sampling.
+    #[cfg(feature = "dataframe")]
+    pub fn to_wide_lazyframe(&self) -> PolarsResult<LazyFrame> {
+        let num_times = self.times.len();
+        let num_rows = self.scenarios.len() * num_times;
+        let mut columns = vec![
+            Column::new(
+                "scenario".into(),
+                self.scenarios
+                    .iter()
+                    .flat_map(|s| std::iter::repeat_n(*s, num_times))
+                    .collect::<Vec<i32>>(),
+            ),
+            Column::new(
+                "time".into(),
+                (0..self.scenarios.len())
+                    .flat_map(|_| self.times.iter().map(|t| t.0))
+                    .collect::<Vec<f64>>(),
+            ),
+        ];
+        for (p_idx, name) in self.process_names.iter().enumerate() {
+            let cells = (0..num_rows).map(|row| (row / num_times, row % num_times));
+            let column = match self.value_kinds[p_idx] {
+                ValueKind::Integer => Column::new(
+                    name.as_str().into(),
+                    cells
+                        .map(|(s_idx, t_idx)| self.value_as_int(s_idx, t_idx, p_idx))
+                        .collect::<Vec<Option<i64>>>(),
+                ),
+                ValueKind::Continuous => Column::new(
+                    name.as_str().into(),
+                    cells
+                        .map(|(s_idx, t_idx)| self.get(s_idx, t_idx, p_idx))
+                        .collect::<Vec<f64>>(),
+                ),
+            };
+            columns.push(column);
+        }
+        if let Some(weights) = &self.weights {
+            let weights: Vec<f64> = weights
+                .iter()
+                .flat_map(|w| std::iter::repeat_n(*w, num_times))
+                .collect();
+            columns.push(Column::new("weight".into(), weights));
+        }
+        Ok(DataFrame::new(columns)?.lazy())
+    }
+
+    /// Recorded increment `increment_idx` of the step starting at `time_idx`, if
+    /// the simulation was run with increment recording.
+    pub fn get_increment(
+        &self,
+        scenario_idx: usize,
+        time_idx: usize,
+        increment_idx: usize,
+    ) -> Option<f64> {
+        let num_increments = self.increment_names.len();
+        let steps = self.times.len() - 1;
+        self.increments
+            .as_ref()
+            .map(|incs| incs[(scenario_idx * steps + time_idx) * num_increments + increment_idx])
+    }
+
+    /// Long-format frame of the recorded increments with columns `scenario`,
+    /// `time` (start of the step), `increment_name` and `value`.
+    #[cfg(feature = "dataframe")]
+    pub fn increments_lazyframe(&self) -> Result<LazyFrame, String> {
+        let increments = self
+            .increments
+            .as_ref()
+            .ok_or("Increments were not recorded for this simulation")?;
+        let num_increments = self.increment_names.len();
+        let steps = self.times.len().saturating_sub(1);
+        let rows_per_scenario = steps * num_increments;
+
+        let scenarios: Vec<i32> = self
+            .scenarios
+            .iter()
+            .flat_map(|s| std::iter::repeat_n(*s, rows_per_scenario))
+            .collect();
+        let times: Vec<f64> = self
+            .scenarios
+            .iter()
+            .flat_map(|_| {
+                self.times[..steps]
+                    .iter()
+                    .flat_map(|t| std::iter::repeat_n(t.0, num_increments))
+            })
+            .collect();
+        let names: Series = StringChunked::from_iter(
+            (0..self.scenarios.len() * steps)
+                .flat_map(|_| self.increment_names.iter().map(|n| n.as_str())),
+        )
+        .with_name("increment_name".into())
+        .into_series();
+
+        df![
+            "scenario" => scenarios,
+            "time" => times,
+            "increment_name" => names,
+            "value" => increments
+        ]
+        .map(|df| df.lazy())
+        .map_err(|e| e.to_string())
+    }
+}
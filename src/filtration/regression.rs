@@ -0,0 +1,190 @@
+use crate::filtration::Filtration;
+use crate::math::linalg::least_squares;
+use ordered_float::OrderedFloat;
+
+/// Basis functions used to build the regression design matrix from predictors.
+#[derive(Clone, Debug)]
+pub enum Basis {
+    /// Powers `x_i^k` for `k = 0..=degree` of every predictor. With
+    /// `cross_terms` set, all mixed monomials of total degree at most `degree`
+    /// are included as well.
+    Polynomial { degree: usize, cross_terms: bool },
+}
+
+impl Basis {
+    /// Exponent vectors (one entry per predictor) of every basis term, starting
+    /// with the intercept.
+    pub fn terms(&self, num_predictors: usize) -> Vec<Vec<usize>> {
+        match self {
+            Basis::Polynomial {
+                degree,
+                cross_terms,
+            } => {
+                let mut terms = vec![vec![0; num_predictors]];
+                if *cross_terms {
+                    let mut current = vec![0; num_predictors];
+                    collect_monomials(0, *degree, &mut current, &mut terms);
+                } else {
+                    for p in 0..num_predictors {
+                        for k in 1..=*degree {
+                            let mut exponents = vec![0; num_predictors];
+                            exponents[p] = k;
+                            terms.push(exponents);
+                        }
+                    }
+                }
+                terms
+            }
+        }
+    }
+
+    fn evaluate(terms: &[Vec<usize>], predictors: &[f64], out: &mut Vec<f64>) {
+        for exponents in terms {
+            out.push(
+                exponents
+                    .iter()
+                    .zip(predictors.iter())
+                    .map(|(&e, &x)| x.powi(e as i32))
+                    .product(),
+            );
+        }
+    }
+}
+
+/// Enumerates every non-constant monomial of total degree at most `remaining`.
+fn collect_monomials(
+    predictor: usize,
+    remaining: usize,
+    current: &mut Vec<usize>,
+    out: &mut Vec<Vec<usize>>,
+) {
+    if predictor == current.len() {
+        if current.iter().any(|&e| e > 0) {
+            out.push(current.clone());
+        }
+        return;
+    }
+    for e in 0..=remaining {
+        current[predictor] = e;
+        collect_monomials(predictor + 1, remaining - e, current, out);
+    }
+    current[predictor] = 0;
+}
+
+/// Outcome of a cross-sectional least-squares regression.
+#[derive(Clone, Debug)]
+pub struct RegressionResult {
+    pub predictors: Vec<String>,
+    /// Exponents of each basis term, aligned with `coefficients`.
+    pub terms: Vec<Vec<usize>>,
+    pub coefficients: Vec<f64>,
+    /// Fitted value for every scenario, in scenario order.
+    pub fitted: Vec<f64>,
+    pub r_squared: f64,
+}
+
+impl RegressionResult {
+    /// Evaluates the fitted regression function at the given predictor values.
+    pub fn predict(&self, predictors: &[f64]) -> f64 {
+        let mut row = Vec::with_capacity(self.terms.len());
+        Basis::evaluate(&self.terms, predictors, &mut row);
+        row.iter()
+            .zip(self.coefficients.iter())
+            .map(|(x, b)| x * b)
+            .sum()
+    }
+}
+
+impl Filtration {
+    /// Regresses `response` (one value per scenario) on basis functions of the
+    /// predictor processes' values at `time`, across all scenarios.
+    pub fn regress(
+        &self,
+        time: f64,
+        response: &[f64],
+        predictors: &[&str],
+        basis: &Basis,
+    ) -> Result<RegressionResult, String> {
+        let num_scenarios = self.scenarios.len();
+        if response.len() != num_scenarios {
+            return Err(format!(
+                "Response has {} values but the filtration has {} scenarios",
+                response.len(),
+                num_scenarios
+            ));
+        }
+        let t_idx = *self
+            .get_time_idx(OrderedFloat(time))
+            .ok_or_else(|| format!("Time {} is not part of the filtration", time))?;
+        let p_indices = predictors
+            .iter()
+            .map(|name| {
+                self.get_process_idx(name)
+                    .copied()
+                    .ok_or_else(|| format!("Unknown predictor process '{}'", name))
+            })
+            .collect::<Result<Vec<usize>, String>>()?;
+
+        let terms = basis.terms(predictors.len());
+        let cols = terms.len();
+        let mut design = Vec::with_capacity(num_scenarios * cols);
+        let mut point = vec![0.0; predictors.len()];
+        for s_idx in 0..num_scenarios {
+            for (k, &p_idx) in p_indices.iter().enumerate() {
+                point[k] = self.get(s_idx, t_idx, p_idx);
+            }
+            Basis::evaluate(&terms, &point, &mut design);
+        }
+
+        let coefficients = least_squares(&design, num_scenarios, cols, response)?;
+        let fitted: Vec<f64> = design
+            .chunks(cols)
+            .map(|row| {
+                row.iter()
+                    .zip(coefficients.iter())
+                    .map(|(x, b)| x * b)
+                    .sum()
+            })
+            .collect();
+
+        let mean = response.iter().sum::<f64>() / num_scenarios.max(1) as f64;
+        let ss_tot: f64 = response.iter().map(|y| (y - mean).powi(2)).sum();
+        let ss_res: f64 = response
+            .iter()
+            .zip(fitted.iter())
+            .map(|(y, f)| (y - f).powi(2))
+            .sum();
+        let r_squared = if ss_tot > 0.0 {
+            1.0 - ss_res / ss_tot
+        } else {
+            1.0
+        };
+
+        Ok(RegressionResult {
+            predictors: predictors.iter().map(|p| p.to_string()).collect(),
+            terms,
+            coefficients,
+            fitted,
+            r_squared,
+        })
+    }
+
+    /// Estimates `E[process(horizon_time) | process(eval_time)]` for every
+    /// scenario by regressing the future value on a basis of the current one.
+    pub fn conditional_expectation(
+        &self,
+        process: &str,
+        horizon_time: f64,
+        eval_time: f64,
+        basis: &Basis,
+    ) -> Result<RegressionResult, String> {
+        if horizon_time < eval_time {
+            return Err(format!(
+                "Horizon time {} lies before evaluation time {}",
+                horizon_time, eval_time
+            ));
+        }
+        let response = self.process_values(horizon_time, process)?;
+        self.regress(eval_time, &response, &[process], basis)
+    }
+}
@@ -0,0 +1,65 @@
+use crate::filtration::Filtration;
+use crate::math::stats;
+use polars::prelude::*;
+
+impl Filtration {
+    /// Cross-scenario summary statistics for every (time, process) pair with
+    /// columns `time`, `process_name`, `mean`, `std`, `min`, `max` and one
+    /// `q{level}` column per requested quantile (e.g. `q0.95`). Under importance
+    /// sampling a `weighted_mean` column holds the likelihood-ratio weighted
+    /// mean, the unbiased estimate under the original measure.
+    pub fn statistics(&self, quantiles: &[f64]) -> PolarsResult<DataFrame> {
+        if let Some(q) = quantiles.iter().find(|q| !(0.0..=1.0).contains(*q)) {
+            polars_bail!(ComputeError: "quantile level {} must lie in [0, 1]", q);
+        }
+        let num_rows = self.times.len() * self.process_names.len();
+        let mut times = Vec::with_capacity(num_rows);
+        let mut names = Vec::with_capacity(num_rows);
+        let mut means = Vec::with_capacity(num_rows);
+        let mut stds = Vec::with_capacity(num_rows);
+        let mut mins = Vec::with_capacity(num_rows);
+        let mut maxs = Vec::with_capacity(num_rows);
+        let mut quantile_values = vec![Vec::with_capacity(num_rows); quantiles.len()];
+        let mut weighted_means = Vec::with_capacity(num_rows);
+
+        let mut cross_section = Vec::with_capacity(self.num_scenarios());
+        for (t_idx, t) in self.times.iter().enumerate() {
+            for (p_idx, p_name) in self.process_names.iter().enumerate() {
+                cross_section.clear();
+                cross_section
+                    .extend((0..self.num_scenarios()).map(|s_idx| self.get(s_idx, t_idx, p_idx)));
+                if let Some(weights) = &self.weights {
+                    let weighted: f64 = cross_section.iter().zip(weights).map(|(x, w)| x * w).sum();
+                    weighted_means.push(weighted / cross_section.len() as f64);
+                }
+                cross_section.sort_by(|a, b| a.total_cmp(b));
+
+                times.push(t.0);
+                names.push(p_name.as_str());
+                means.push(stats::mean(&cross_section));
+                stds.push(stats::std_dev(&cross_section));
+                mins.push(cross_section.first().copied().unwrap_or(f64::NAN));
+                maxs.push(cross_section.last().copied().unwrap_or(f64::NAN));
+                for (q, values) in quantiles.iter().zip(quantile_values.iter_mut()) {
+                    values.push(stats::quantile_sorted(&cross_section, *q));
+                }
+            }
+        }
+
+        let mut columns = vec![
+            Column::new("time".into(), times),
+            Column::new("process_name".into(), names),
+            Column::new("mean".into(), means),
+            Column::new("std".into(), stds),
+            Column::new("min".into(), mins),
+            Column::new("max".into(), maxs),
+        ];
+        if self.weights.is_some() {
+            columns.push(Column::new("weighted_mean".into(), weighted_means));
+        }
+        for (q, values) in quantiles.iter().zip(quantile_values) {
+            columns.push(Column::new(format!("q{}", q).into(), values));
+        }
+        DataFrame::new(columns)
+    }
+}
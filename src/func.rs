@@ -1,11 +1,224 @@
 use crate::filtration::ScenarioFiltration;
+use crate::math::stats;
 use fasteval::{Compiler, Evaler, Instruction, Slab};
+use lazy_static::lazy_static;
 use ordered_float::OrderedFloat;
+use regex::Regex;
+use std::collections::BTreeSet;
 
+lazy_static! {
+    static ref AGGREGATE_RE: Regex = Regex::new(
+        r"\b(mean|std|quantile)\(\s*([A-Za-z_][A-Za-z0-9_]*)\s*(?:,\s*([0-9]*\.?[0-9]+(?:[eE][-+]?[0-9]+)?)\s*)?\)"
+    )
+    .unwrap();
+    static ref LAG_RE: Regex =
+        Regex::new(r"\blag\(\s*([A-Za-z_][A-Za-z0-9_]*)\s*,\s*([^()]*?)\s*\)").unwrap();
+}
+
+/// Cross-scenario statistic of a process at the current time step.
+#[derive(Clone, Debug, PartialEq)]
+pub enum AggregateKind {
+    Mean,
+    Std,
+    Quantile(f64),
+}
+
+/// A mean-field term such as `mean(X1)`, `std(X1)` or `quantile(X1, 0.95)`.
+///
+/// These are rewritten to plain variables (`var_name`) when the expression is
+/// compiled; the simulation fills them in once per time step across all
+/// scenarios, which is only possible in time-major order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Aggregate {
+    pub kind: AggregateKind,
+    pub process: String,
+    pub var_name: String,
+}
+
+impl Aggregate {
+    /// Evaluates the statistic over the cross-section `values` (reordered in place).
+    pub fn compute(&self, values: &mut [f64]) -> f64 {
+        match self.kind {
+            AggregateKind::Mean => stats::mean(values),
+            AggregateKind::Std => stats::std_dev(values),
+            AggregateKind::Quantile(q) => stats::quantile(values, q),
+        }
+    }
+}
+
+/// Rewrites every `if(cond, a, b)` into an expression fasteval evaluates
+/// natively. `cond` counts as true when non-zero; comparisons such as `X1 < 1.0`
+/// or `t >= 5` evaluate to 1 or 0. Only the selected branch is evaluated, as
+/// `&&` short-circuits and yields its right operand.
+fn rewrite_conditionals(expr_str: &str) -> Result<String, String> {
+    let mut out = String::with_capacity(expr_str.len());
+    let mut rest = expr_str;
+    while let Some(pos) = find_call(rest, "if") {
+        out.push_str(&rest[..pos]);
+        let open = pos + rest[pos..].find('(').unwrap_or(0);
+        let (args, len) = split_call_args(&rest[open..])
+            .ok_or_else(|| format!("Unbalanced parentheses in '{}'", &rest[pos..]))?;
+        let [cond, a, b] = args.as_slice() else {
+            return Err(format!(
+                "if(cond, a, b) takes 3 arguments, got {} in '{}'",
+                args.len(),
+                &rest[pos..open + len]
+            ));
+        };
+        let (cond, a, b) = (
+            rewrite_conditionals(cond)?,
+            rewrite_conditionals(a)?,
+            rewrite_conditionals(b)?,
+        );
+        out.push_str(&format!(
+            "((({cond}) != 0 && ({a})) + (({cond}) == 0 && ({b})))"
+        ));
+        rest = &rest[open + len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Byte offset of the first call `name(` in `s` that is not part of a longer
+/// identifier.
+fn find_call(s: &str, name: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_alphanumeric() || c == '_';
+    s.match_indices(name).map(|(i, _)| i).find(|&i| {
+        let before_ok = s[..i].chars().next_back().is_none_or(|c| !is_ident(c));
+        let after = s[i + name.len()..].trim_start();
+        before_ok && after.starts_with('(')
+    })
+}
+
+/// Splits `(a, b, ...)` at top-level commas; returns the arguments and the
+/// length of the parenthesised block, or `None` if unbalanced.
+fn split_call_args(s: &str) -> Option<(Vec<String>, usize)> {
+    let mut depth = 0usize;
+    let mut args = Vec::new();
+    let mut start = 1;
+    for (i, c) in s.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    args.push(s[start..i].trim().to_string());
+                    return Some((args, i + 1));
+                }
+            }
+            ',' if depth == 1 => {
+                args.push(s[start..i].trim().to_string());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// A delayed state reference `lag(X1, 1.0)`: the value of `X1` at `t - lag`.
+///
+/// Like aggregates, it is rewritten to a plain variable that the filtration
+/// fills in from the stored path whenever its cache is refreshed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Lag {
+    pub process: String,
+    pub lag: f64,
+    pub var_name: String,
+}
+
+/// Replaces every `lag(X, L)` call in `expr_str` by its placeholder variable.
+fn rewrite_lags(expr_str: &str) -> Result<(String, Vec<Lag>), String> {
+    let mut lags: Vec<Lag> = Vec::new();
+    let mut error = None;
+    let rewritten = LAG_RE.replace_all(expr_str, |caps: &regex::Captures| {
+        let process = caps[1].to_string();
+        let lag = match caps[2].parse::<f64>() {
+            Ok(lag) if lag >= 0.0 && lag.is_finite() => lag,
+            _ => {
+                error = Some(format!(
+                    "Lag in '{}' must be a non-negative numeric literal",
+                    &caps[0]
+                ));
+                0.0
+            }
+        };
+        let var_name = format!("__lag_{}_{}", process, lag.to_string().replace('.', "p"));
+        if !lags.iter().any(|l| l.var_name == var_name) {
+            lags.push(Lag {
+                process,
+                lag,
+                var_name: var_name.clone(),
+            });
+        }
+        var_name
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok((rewritten.into_owned(), lags)),
+    }
+}
+
+/// Replaces every aggregate call in `expr_str` by its placeholder variable.
+fn rewrite_aggregates(expr_str: &str) -> Result<(String, Vec<Aggregate>), String> {
+    let mut aggregates: Vec<Aggregate> = Vec::new();
+    let mut error = None;
+    let rewritten = AGGREGATE_RE.replace_all(expr_str, |caps: &regex::Captures| {
+        let process = caps[2].to_string();
+        let kind = match (&caps[1], caps.get(3)) {
+            ("mean", None) => AggregateKind::Mean,
+            ("std", None) => AggregateKind::Std,
+            ("quantile", Some(q)) => match q.as_str().parse::<f64>() {
+                Ok(q) if (0.0..=1.0).contains(&q) => AggregateKind::Quantile(q),
+                _ => {
+                    error = Some(format!(
+                        "Quantile level must lie in [0, 1] in '{}'",
+                        &caps[0]
+                    ));
+                    AggregateKind::Mean
+                }
+            },
+            _ => {
+                error = Some(format!("Invalid aggregate '{}'", &caps[0]));
+                AggregateKind::Mean
+            }
+        };
+        let var_name = match kind {
+            AggregateKind::Mean => format!("__mean_{}", process),
+            AggregateKind::Std => format!("__std_{}", process),
+            AggregateKind::Quantile(q) => {
+                format!("__quantile_{}_{}", process, q.to_string().replace('.', "p"))
+            }
+        };
+        if !aggregates.iter().any(|a| a.var_name == var_name) {
+            aggregates.push(Aggregate {
+                kind,
+                process,
+                var_name: var_name.clone(),
+            });
+        }
+        var_name
+    });
+    match error {
+        Some(e) => Err(e),
+        None => Ok((rewritten.into_owned(), aggregates)),
+    }
+}
+
+/// A compiled coefficient expression.
+///
+/// Besides fasteval's arithmetic and functions, expressions may use:
+/// - comparisons (`<`, `<=`, `==`, `!=`, `>=`, `>`), which evaluate to 1 or 0 and
+///   can be used as indicators, e.g. `0.3 * (X1 < 1.0) + 0.15 * (X1 >= 1.0)`;
+/// - `if(cond, a, b)`, e.g. `if(t < 5, 0.05 * X1, 0)`;
+/// - delayed values `lag(X1, 1.0)`;
+/// - mean-field terms `mean(X1)`, `std(X1)` and `quantile(X1, q)`.
 pub struct Function {
     instruction: Instruction,
     slab: Slab,
     expr_str: String,
+    aggregates: Vec<Aggregate>,
+    lags: Vec<Lag>,
 }
 
 impl Clone for Function {
@@ -16,19 +229,53 @@ impl Clone for Function {
 
 impl Function {
     pub fn new(expr_str: &str) -> Result<Self, String> {
+        let rewritten = rewrite_conditionals(expr_str)?;
+        let (rewritten, lags) = rewrite_lags(&rewritten)?;
+        let (rewritten, aggregates) = rewrite_aggregates(&rewritten)?;
         let parser = fasteval::Parser::new();
         let mut slab = Slab::new();
         let expr = parser
-            .parse(expr_str, &mut slab.ps)
+            .parse(&rewritten, &mut slab.ps)
             .map_err(|e| format!("Parse Error: {:?}", e))?;
         let instruction = expr.from(&slab.ps).compile(&slab.ps, &mut slab.cs);
         Ok(Self {
             instruction,
             slab,
             expr_str: expr_str.to_string(),
+            aggregates,
+            lags,
         })
     }
 
+    /// The expression as written.
+    pub fn source(&self) -> &str {
+        &self.expr_str
+    }
+
+    /// Value of an expression that reads no variables, e.g. `1` or `2 * 3`.
+    pub fn constant(&self) -> Option<f64> {
+        match self.instruction {
+            Instruction::IConst(c) => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Mean-field terms referenced by this expression.
+    pub fn aggregates(&self) -> &[Aggregate] {
+        &self.aggregates
+    }
+
+    /// Names of the variables and custom functions the expression refers to,
+    /// including placeholders of rewritten terms.
+    pub fn variables(&self) -> BTreeSet<String> {
+        self.instruction.var_names(&self.slab)
+    }
+
+    /// Delayed state references used by this expression.
+    pub fn lags(&self) -> &[Lag] {
+        &self.lags
+    }
+
     pub fn eval(
         &self,
         t: OrderedFloat<f64>,
@@ -1,7 +1,17 @@
 extern crate lazy_static;
 
+// No OS entropy or Python on wasm32-unknown-unknown; build for it with
+// `--no-default-features` and explicit seeds.
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "os-rng"))]
+compile_error!(
+    "the os-rng feature is not supported on wasm32-unknown-unknown, set seeds explicitly"
+);
+#[cfg(all(target_arch = "wasm32", feature = "python"))]
+compile_error!("the python feature is not supported on wasm32");
+
 pub mod filtration;
 pub mod func;
+pub mod math;
 pub mod proc;
 pub mod rng;
 pub mod sim;
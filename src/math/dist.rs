@@ -0,0 +1,100 @@
+/// Evaluates a polynomial with coefficients ordered from the highest degree.
+#[inline]
+fn horner(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().fold(0.0, |acc, c| acc * x + c)
+}
+
+/// Standard normal cumulative distribution function, accurate to about 1e-15
+/// (Hart's algorithm as given by West, 2005).
+pub fn normal_cdf(x: f64) -> f64 {
+    const NUM: [f64; 7] = [
+        3.526_249_659_989_11e-2,
+        0.700_383_064_443_688,
+        6.373_962_203_531_65,
+        33.912_866_078_383,
+        112.079_291_497_871,
+        221.213_596_169_931,
+        220.206_867_912_376,
+    ];
+    const DEN: [f64; 8] = [
+        8.838_834_764_831_84e-2,
+        1.755_667_163_182_64,
+        16.064_177_579_207,
+        86.780_732_202_946_1,
+        296.564_248_779_674,
+        637.333_633_378_831,
+        793.826_512_519_948,
+        440.413_735_824_752,
+    ];
+    let z = x.abs();
+    let tail = if z > 37.0 {
+        0.0
+    } else {
+        let e = (-0.5 * z * z).exp();
+        if z < 7.071_067_811_865_47 {
+            e * horner(&NUM, z) / horner(&DEN, z)
+        } else {
+            let b = z + 1.0 / (z + 2.0 / (z + 3.0 / (z + 4.0 / (z + 0.65))));
+            e / (b * 2.506_628_274_631)
+        }
+    };
+    if x > 0.0 { 1.0 - tail } else { tail }
+}
+
+/// Inverse of [`normal_cdf`]: Acklam's rational approximation refined by one
+/// Halley step, accurate to near machine precision. Returns `±inf` at 0 and 1.
+pub fn inverse_normal_cdf(p: f64) -> f64 {
+    if p <= 0.0 {
+        return f64::NEG_INFINITY;
+    }
+    if p >= 1.0 {
+        return f64::INFINITY;
+    }
+    const A: [f64; 6] = [
+        -3.969_683_028_665_376e1,
+        2.209_460_984_245_205e2,
+        -2.759_285_104_469_687e2,
+        1.383_577_518_672_69e2,
+        -3.066_479_806_614_716e1,
+        2.506_628_277_459_239,
+    ];
+    const B: [f64; 5] = [
+        -5.447_609_879_822_406e1,
+        1.615_858_368_580_409e2,
+        -1.556_989_798_598_866e2,
+        6.680_131_188_771_972e1,
+        -1.328_068_155_288_572e1,
+    ];
+    const C: [f64; 6] = [
+        -7.784_894_002_430_293e-3,
+        -3.223_964_580_411_365e-1,
+        -2.400_758_277_161_838,
+        -2.549_732_539_343_734,
+        4.374_664_141_464_968,
+        2.938_163_982_698_783,
+    ];
+    const D: [f64; 4] = [
+        7.784_695_709_041_462e-3,
+        3.224_671_290_700_398e-1,
+        2.445_134_137_142_996,
+        3.754_408_661_907_416,
+    ];
+    const P_LOW: f64 = 0.024_25;
+
+    let x = if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        horner(&C, q) / (horner(&D, q) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        horner(&A, r) * q / (horner(&B, r) * r + 1.0)
+    } else {
+        let q = (-2.0 * (1.0 - p).ln()).sqrt();
+        -horner(&C, q) / (horner(&D, q) * q + 1.0)
+    };
+
+    // One step of Halley's method against the accurate CDF
+    let e = normal_cdf(x) - p;
+    let u = e * (2.0 * std::f64::consts::PI).sqrt() * (0.5 * x * x).exp();
+    x - u / (1.0 + 0.5 * x * u)
+}
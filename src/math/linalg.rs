@@ -0,0 +1,128 @@
+/// Cholesky factorisation of a symmetric positive definite `n x n` matrix stored
+/// row-major. Returns the lower triangular factor `L` (row-major, `A = L L^T`),
+/// or `None` when a non-positive pivot is encountered.
+pub fn cholesky(a: &[f64], n: usize) -> Option<Vec<f64>> {
+    debug_assert_eq!(a.len(), n * n);
+    let mut l = vec![0.0; n * n];
+    for i in 0..n {
+        for j in 0..=i {
+            let mut sum = a[i * n + j];
+            for k in 0..j {
+                sum -= l[i * n + k] * l[j * n + k];
+            }
+            if i == j {
+                if sum <= 0.0 || !sum.is_finite() {
+                    return None;
+                }
+                l[i * n + i] = sum.sqrt();
+            } else {
+                l[i * n + j] = sum / l[j * n + j];
+            }
+        }
+    }
+    Some(l)
+}
+
+/// Solves `L L^T x = b` given the lower triangular factor from [`cholesky`].
+pub fn cholesky_solve(l: &[f64], n: usize, b: &[f64]) -> Vec<f64> {
+    // Forward substitution: L y = b
+    let mut y = vec![0.0; n];
+    for i in 0..n {
+        let mut sum = b[i];
+        for k in 0..i {
+            sum -= l[i * n + k] * y[k];
+        }
+        y[i] = sum / l[i * n + i];
+    }
+    // Back substitution: L^T x = y
+    let mut x = vec![0.0; n];
+    for i in (0..n).rev() {
+        let mut sum = y[i];
+        for k in (i + 1)..n {
+            sum -= l[k * n + i] * x[k];
+        }
+        x[i] = sum / l[i * n + i];
+    }
+    x
+}
+
+/// Least-squares solution of `X beta = y` for a row-major `rows x cols` design
+/// matrix via the normal equations.
+///
+/// Columns are rescaled to unit RMS before forming `X^T X` so that polynomial
+/// bases of very different magnitude stay well conditioned. If the Gram matrix
+/// is (numerically) singular, e.g. because a predictor is constant, a small
+/// ridge penalty is added and increased until the factorisation succeeds.
+pub fn least_squares(x: &[f64], rows: usize, cols: usize, y: &[f64]) -> Result<Vec<f64>, String> {
+    if x.len() != rows * cols || y.len() != rows {
+        return Err(format!(
+            "Design matrix of size {} does not match {} rows x {} columns with {} responses",
+            x.len(),
+            rows,
+            cols,
+            y.len()
+        ));
+    }
+    if cols == 0 {
+        return Ok(Vec::new());
+    }
+
+    let mut scales = vec![0.0; cols];
+    for r in 0..rows {
+        for c in 0..cols {
+            scales[c] += x[r * cols + c] * x[r * cols + c];
+        }
+    }
+    for s in scales.iter_mut() {
+        *s = (*s / rows.max(1) as f64).sqrt();
+        if *s == 0.0 || !s.is_finite() {
+            *s = 1.0;
+        }
+    }
+
+    let mut gram = vec![0.0; cols * cols];
+    let mut rhs = vec![0.0; cols];
+    for r in 0..rows {
+        let row = &x[r * cols..(r + 1) * cols];
+        for i in 0..cols {
+            let xi = row[i] / scales[i];
+            rhs[i] += xi * y[r];
+            for j in 0..=i {
+                gram[i * cols + j] += xi * row[j] / scales[j];
+            }
+        }
+    }
+    for i in 0..cols {
+        for j in 0..i {
+            gram[j * cols + i] = gram[i * cols + j];
+        }
+    }
+
+    let max_diag = (0..cols)
+        .map(|i| gram[i * cols + i])
+        .fold(0.0_f64, f64::max)
+        .max(f64::MIN_POSITIVE);
+    let mut ridge = 0.0;
+    for _ in 0..8 {
+        let mut regularised = gram.clone();
+        for i in 0..cols {
+            regularised[i * cols + i] += ridge;
+        }
+        // Pivots that are tiny relative to the largest diagonal entry indicate
+        // (near-)collinear columns; treat those like a failed factorisation.
+        if let Some(l) = cholesky(&regularised, cols)
+            .filter(|l| (0..cols).all(|i| l[i * cols + i] * l[i * cols + i] > 1e-13 * max_diag))
+        {
+            let beta = cholesky_solve(&l, cols, &rhs);
+            if beta.iter().all(|b| b.is_finite()) {
+                return Ok(beta.iter().zip(scales.iter()).map(|(b, s)| b / s).collect());
+            }
+        }
+        ridge = if ridge == 0.0 {
+            1e-12 * max_diag
+        } else {
+            ridge * 100.0
+        };
+    }
+    Err("Least-squares system is singular even after ridge regularisation".into())
+}
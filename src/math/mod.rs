@@ -0,0 +1,3 @@
+pub mod dist;
+pub mod linalg;
+pub mod stats;
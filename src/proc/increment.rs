@@ -2,6 +2,24 @@ use crate::filtration::ScenarioFiltration;
 use crate::func::Function;
 use crate::rng::BaseRng;
 use ordered_float::OrderedFloat;
+use std::sync::Mutex;
+
+/// Index of a stochastic driver in the process universe's stochastic registry.
+pub type IncrementId = usize;
+
+/// What kind of term an incrementor drives, so schemes and validators can tell
+/// drift, diffusion and jump terms apart.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum IncrementKind {
+    /// The deterministic `dt` term.
+    Time,
+    /// A Brownian driver `dW`.
+    Wiener,
+    /// A pure-jump driver such as `dN`.
+    Jump,
+    /// Anything else, e.g. a user-registered incrementor.
+    Other,
+}
 
 pub trait Incrementor: Send + Sync + std::fmt::Debug {
     fn sample(
@@ -11,7 +29,23 @@ pub trait Incrementor: Send + Sync + std::fmt::Debug {
         rng: &mut dyn BaseRng,
     ) -> f64;
     fn clone_box(&self) -> Box<dyn Incrementor>;
+    fn kind(&self) -> IncrementKind {
+        IncrementKind::Other
+    }
     fn is_wiener(&self) -> bool {
+        self.kind() == IncrementKind::Wiener
+    }
+    /// Index of the stochastic driver in the process universe's stochastic
+    /// registry, `None` for deterministic increments such as `dt`.
+    fn stochastic_idx(&self) -> Option<usize> {
+        None
+    }
+    /// Expressions evaluated by the incrementor itself, e.g. a jump intensity.
+    fn functions(&self) -> Vec<&Function> {
+        Vec::new()
+    }
+    /// Whether every sampled increment is a whole number, e.g. a jump count.
+    fn is_integer_valued(&self) -> bool {
         false
     }
 }
@@ -53,6 +87,9 @@ impl Incrementor for TimeIncrementor {
     ) -> f64 {
         self.dts[time_idx]
     }
+    fn kind(&self) -> IncrementKind {
+        IncrementKind::Time
+    }
     fn clone_box(&self) -> Box<dyn Incrementor> {
         Box::new(self.clone())
     }
@@ -82,19 +119,24 @@ impl WienerIncrementor {
 }
 
 impl Incrementor for WienerIncrementor {
-    #[inline]
-    fn is_wiener(&self) -> bool {
-        true
+    fn kind(&self) -> IncrementKind {
+        IncrementKind::Wiener
     }
     fn sample(
         &self,
         time_idx: usize,
-        _filtration: &mut ScenarioFiltration,
+        filtration: &mut ScenarioFiltration,
         rng: &mut dyn BaseRng,
     ) -> f64 {
+        if let Some(dw) = filtration.matched_increment(time_idx, self.idx) {
+            return dw;
+        }
         let q = rng.sample(time_idx, self.idx);
         self.sqrt_dts[time_idx] * fast_inverse_normal_cdf(q)
     }
+    fn stochastic_idx(&self) -> Option<usize> {
+        Some(self.idx)
+    }
     fn clone_box(&self) -> Box<dyn Incrementor> {
         Box::new(Self {
             idx: self.idx,
@@ -146,6 +188,18 @@ impl Incrementor for PoissonJumpIncrementor {
         let effective_lambda = self.lambda.eval(t, filtration).unwrap() * dt;
         fast_inverse_poisson_cdf(u, effective_lambda) as f64
     }
+    fn kind(&self) -> IncrementKind {
+        IncrementKind::Jump
+    }
+    fn functions(&self) -> Vec<&Function> {
+        vec![self.lambda.as_ref()]
+    }
+    fn is_integer_valued(&self) -> bool {
+        true
+    }
+    fn stochastic_idx(&self) -> Option<usize> {
+        Some(self.idx)
+    }
     fn clone_box(&self) -> Box<dyn Incrementor> {
         Box::new(Self {
             lambda: self.lambda.clone(),
@@ -156,6 +210,148 @@ impl Incrementor for PoissonJumpIncrementor {
     }
 }
 
+/// Increment of a two-state continuous-time Markov chain with regimes 0 and 1.
+///
+/// Registered under a custom prefix (e.g. `dM`) it lets an equation such as
+/// `dR = (1) * dM1(p01=0.5,p10=2.0)` track the regime, which other coefficients
+/// can then use to switch volatility, e.g. `(0.1 + 0.2 * R) * dW1`. The rates
+/// `p01` and `p10` are per unit time; `r0` optionally sets the starting regime.
+/// The increment is +1 on a 0 -> 1 switch, -1 on a 1 -> 0 switch, 0 otherwise.
+pub struct MarkovRegimeIncrementor {
+    idx: usize,
+    rate_01: f64,
+    rate_10: f64,
+    initial_regime: usize,
+    dts: Vec<f64>,
+    state: Mutex<MarkovRegimeState>,
+}
+
+struct MarkovRegimeState {
+    regime: usize,
+    last_sample: Option<(usize, f64)>,
+}
+
+impl std::fmt::Debug for MarkovRegimeIncrementor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("dM")
+            .field("idx", &self.idx)
+            .field("p01", &self.rate_01)
+            .field("p10", &self.rate_10)
+            .finish()
+    }
+}
+
+impl MarkovRegimeIncrementor {
+    pub fn new(
+        idx: usize,
+        rate_01: f64,
+        rate_10: f64,
+        initial_regime: usize,
+        timesteps: &[OrderedFloat<f64>],
+    ) -> Result<Self, String> {
+        if rate_01 < 0.0 || rate_10 < 0.0 {
+            return Err("Transition rates must be non-negative".into());
+        }
+        if initial_regime > 1 {
+            return Err("Initial regime must be 0 or 1".into());
+        }
+        let dts = timesteps
+            .windows(2)
+            .map(|w| (w[1] - w[0]).into_inner())
+            .collect();
+        Ok(Self {
+            idx,
+            rate_01,
+            rate_10,
+            initial_regime,
+            dts,
+            state: Mutex::new(MarkovRegimeState {
+                regime: initial_regime,
+                last_sample: None,
+            }),
+        })
+    }
+
+    /// Factory suitable for `IncrementorRegistry::register`, reading the
+    /// `p01`, `p10` and optional `r0` arguments.
+    pub fn from_args(
+        args: &str,
+        idx: usize,
+        timesteps: &[OrderedFloat<f64>],
+    ) -> Result<Box<dyn Incrementor>, String> {
+        let args = crate::proc::util::parse_named_args(args)?;
+        let rate = |key: &str| {
+            args.get(key)
+                .copied()
+                .ok_or_else(|| format!("Missing argument '{}'", key))
+        };
+        let initial_regime = args.get("r0").copied().unwrap_or(0.0) as usize;
+        Ok(Box::new(Self::new(
+            idx,
+            rate("p01")?,
+            rate("p10")?,
+            initial_regime,
+            timesteps,
+        )?))
+    }
+}
+
+impl Incrementor for MarkovRegimeIncrementor {
+    fn sample(
+        &self,
+        time_idx: usize,
+        _filtration: &mut ScenarioFiltration,
+        rng: &mut dyn BaseRng,
+    ) -> f64 {
+        let mut state = self.state.lock().unwrap();
+        if let Some((last_idx, increment)) = state.last_sample
+            && last_idx == time_idx
+        {
+            return increment;
+        }
+        if time_idx == 0 {
+            state.regime = self.initial_regime;
+        }
+        let rate = if state.regime == 0 {
+            self.rate_01
+        } else {
+            self.rate_10
+        };
+        let switch_probability = 1.0 - (-rate * self.dts[time_idx]).exp();
+        let increment = if rng.sample(time_idx, self.idx) < switch_probability {
+            let increment = if state.regime == 0 { 1.0 } else { -1.0 };
+            state.regime = 1 - state.regime;
+            increment
+        } else {
+            0.0
+        };
+        state.last_sample = Some((time_idx, increment));
+        increment
+    }
+    fn kind(&self) -> IncrementKind {
+        IncrementKind::Jump
+    }
+    fn stochastic_idx(&self) -> Option<usize> {
+        Some(self.idx)
+    }
+    fn is_integer_valued(&self) -> bool {
+        true
+    }
+    fn clone_box(&self) -> Box<dyn Incrementor> {
+        Box::new(Self {
+            idx: self.idx,
+            rate_01: self.rate_01,
+            rate_10: self.rate_10,
+            initial_regime: self.initial_regime,
+            dts: self.dts.clone(),
+            state: Mutex::new(MarkovRegimeState {
+                regime: self.initial_regime,
+                last_sample: None,
+            }),
+        })
+    }
+}
+
 // Inverse cdff functions
 #[inline]
 fn fast_inverse_normal_cdf(p: f64) -> f64 {
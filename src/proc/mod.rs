@@ -1,8 +1,21 @@
 pub mod increment;
+pub mod model;
 pub mod util;
 
-use crate::func::Function;
-use std::collections::HashMap;
+use crate::func::{Aggregate, Function, Lag};
+use increment::{IncrementId, IncrementKind};
+use std::collections::{BTreeSet, HashMap};
+
+/// Whether a process takes real or whole-number values.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ValueKind {
+    #[default]
+    Continuous,
+    /// Counts, e.g. `dN = (1) * dN1(0.3)`. Values are rounded to the nearest
+    /// integer after every step so scheme arithmetic cannot leave them off by
+    /// an ulp.
+    Integer,
+}
 
 #[derive(Clone)]
 pub struct AlgebraicProcess {
@@ -35,12 +48,69 @@ impl LevyProcess {
         if coefficients.len() != incrementors.len() {
             return Err("Number of coefficients must match incrementors".into());
         }
+        // Keep terms in a canonical order (drift, diffusions, jumps, others) so
+        // that the order in which an equation is written cannot change results.
+        let mut terms: Vec<_> = coefficients.into_iter().zip(incrementors).collect();
+        terms.sort_by_key(|(_, incr)| incr.kind());
+        let (coefficients, incrementors) = terms.into_iter().unzip();
         Ok(Self {
             name,
             coefficients,
             incrementors,
         })
     }
+
+    /// Coefficient of the first `dt` term, if any.
+    pub fn drift(&self) -> Option<&Function> {
+        self.terms_of_kind(IncrementKind::Time)
+            .next()
+            .map(|(c, _)| c)
+    }
+
+    /// Coefficients of the Brownian terms with the driver each one is attached to.
+    pub fn diffusion_terms(&self) -> Vec<(&Function, IncrementId)> {
+        self.stochastic_terms(IncrementKind::Wiener)
+    }
+
+    /// Coefficients of the jump terms with the driver each one is attached to.
+    pub fn jump_terms(&self) -> Vec<(&Function, IncrementId)> {
+        self.stochastic_terms(IncrementKind::Jump)
+    }
+
+    /// `Integer` when every term adds a whole-number increment times an
+    /// integer constant, e.g. `dN = (1) * dN1(0.3) + (-1) * dN2(0.1)`.
+    pub fn value_kind(&self) -> ValueKind {
+        let integer = !self.incrementors.is_empty()
+            && self
+                .coefficients
+                .iter()
+                .zip(&self.incrementors)
+                .all(|(c, incr)| {
+                    incr.is_integer_valued() && c.constant().is_some_and(|c| c.fract() == 0.0)
+                });
+        if integer {
+            ValueKind::Integer
+        } else {
+            ValueKind::Continuous
+        }
+    }
+
+    fn terms_of_kind(
+        &self,
+        kind: IncrementKind,
+    ) -> impl Iterator<Item = (&Function, &dyn increment::Incrementor)> {
+        self.coefficients
+            .iter()
+            .zip(self.incrementors.iter())
+            .filter(move |(_, incr)| incr.kind() == kind)
+            .map(|(c, incr)| (c.as_ref(), incr.as_ref()))
+    }
+
+    fn stochastic_terms(&self, kind: IncrementKind) -> Vec<(&Function, IncrementId)> {
+        self.terms_of_kind(kind)
+            .filter_map(|(c, incr)| Some((c, incr.stochastic_idx()?)))
+            .collect()
+    }
 }
 
 #[derive(Clone)]
@@ -56,6 +126,51 @@ impl Process {
             Process::Algebraic(p) => &p.name,
         }
     }
+
+    /// Drift coefficient; always `None` for algebraic processes.
+    pub fn drift(&self) -> Option<&Function> {
+        match self {
+            Process::Levy(p) => p.drift(),
+            Process::Algebraic(_) => None,
+        }
+    }
+
+    pub fn diffusion_terms(&self) -> Vec<(&Function, IncrementId)> {
+        match self {
+            Process::Levy(p) => p.diffusion_terms(),
+            Process::Algebraic(_) => Vec::new(),
+        }
+    }
+
+    pub fn jump_terms(&self) -> Vec<(&Function, IncrementId)> {
+        match self {
+            Process::Levy(p) => p.jump_terms(),
+            Process::Algebraic(_) => Vec::new(),
+        }
+    }
+
+    /// Value kind inferred from the equation; algebraic processes are always
+    /// continuous.
+    pub fn value_kind(&self) -> ValueKind {
+        match self {
+            Process::Levy(p) => p.value_kind(),
+            Process::Algebraic(_) => ValueKind::Continuous,
+        }
+    }
+
+    /// Every expression evaluated for this process, including those owned by
+    /// its incrementors.
+    pub fn functions(&self) -> Vec<&Function> {
+        match self {
+            Process::Levy(p) => p
+                .coefficients
+                .iter()
+                .map(|c| c.as_ref())
+                .chain(p.incrementors.iter().flat_map(|i| i.functions()))
+                .collect(),
+            Process::Algebraic(p) => p.coefficients.iter().map(|c| c.as_ref()).collect(),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -87,4 +202,42 @@ impl ProcessUniverse {
             algebraic_process_indices,
         }
     }
+
+    /// Distinct mean-field terms used anywhere in the system.
+    pub fn aggregates(&self) -> Vec<Aggregate> {
+        let mut aggregates: Vec<Aggregate> = Vec::new();
+        for func in self.processes.iter().flat_map(|p| p.functions()) {
+            for agg in func.aggregates() {
+                if !aggregates.iter().any(|a| a.var_name == agg.var_name) {
+                    aggregates.push(agg.clone());
+                }
+            }
+        }
+        aggregates
+    }
+
+    /// Distinct delayed state references used anywhere in the system.
+    pub fn lags(&self) -> Vec<Lag> {
+        let mut lags: Vec<Lag> = Vec::new();
+        for func in self.processes.iter().flat_map(|p| p.functions()) {
+            for lag in func.lags() {
+                if !lags.iter().any(|l| l.var_name == lag.var_name) {
+                    lags.push(lag.clone());
+                }
+            }
+        }
+        lags
+    }
+
+    /// Free variables of the system's expressions, i.e. everything that is not
+    /// a process, the time `t` or a placeholder of a rewritten term. Their
+    /// values must be supplied at run time, see `SimulationEngine`.
+    pub fn parameters(&self) -> BTreeSet<String> {
+        self.processes
+            .iter()
+            .flat_map(|p| p.functions())
+            .flat_map(|f| f.variables())
+            .filter(|v| v != "t" && !v.starts_with("__") && !self.process_registry.contains_key(v))
+            .collect()
+    }
 }
@@ -0,0 +1,269 @@
+use crate::proc::increment::IncrementKind;
+use crate::proc::util::split_annotation;
+use crate::proc::{Process, ProcessUniverse, ValueKind};
+#[cfg(feature = "dataframe")]
+use polars::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// One `(coefficient) * differential` term of a stochastic equation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TermDescriptor {
+    /// Coefficient expression as written.
+    pub coefficient: String,
+    /// Differential as written, e.g. `dt`, `dW1` or `dN1(0.5)`.
+    pub differential: String,
+    pub kind: IncrementKind,
+    /// Index in the stochastic registry, `None` for `dt`.
+    pub increment_idx: Option<usize>,
+}
+
+/// What was parsed from one equation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ProcessDescriptor {
+    pub name: String,
+    /// The equation as given, including its annotation.
+    pub equation: String,
+    /// `true` for `X = ...` equations, `false` for `dX = ...`.
+    pub algebraic: bool,
+    /// Value kind implied by the equation.
+    pub value_kind: ValueKind,
+    /// Terms in canonical order (drift, diffusions, jumps, others); an
+    /// algebraic process has a single term with differential `""`.
+    pub terms: Vec<TermDescriptor>,
+    /// Processes whose current value the equation reads.
+    pub referenced_processes: BTreeSet<String>,
+    /// Processes read through `lag(...)` or mean-field terms.
+    pub delayed_references: BTreeSet<String>,
+    /// Free parameters the equation reads.
+    pub parameters: BTreeSet<String>,
+    /// Annotation given after `#`, e.g. `# units=USD, desc=spot price`.
+    pub metadata: BTreeMap<String, String>,
+}
+
+/// Introspection view of a parsed system, see
+/// [`parse_model`](crate::proc::util::parse_model).
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedModel {
+    pub processes: Vec<ProcessDescriptor>,
+}
+
+#[cfg(feature = "dataframe")]
+fn kind_name(kind: IncrementKind) -> &'static str {
+    match kind {
+        IncrementKind::Time => "time",
+        IncrementKind::Wiener => "wiener",
+        IncrementKind::Jump => "jump",
+        IncrementKind::Other => "other",
+    }
+}
+
+impl ParsedModel {
+    /// Describes `universe`, parsed from `equations` in the same order.
+    pub fn new(equations: &[String], universe: &ProcessUniverse) -> Result<Self, String> {
+        if equations.len() != universe.processes.len() {
+            return Err(format!(
+                "{} equations given for {} processes",
+                equations.len(),
+                universe.processes.len()
+            ));
+        }
+        let mut increment_names = vec![String::new(); universe.stochastic_registry.len()];
+        for (name, idx) in &universe.stochastic_registry {
+            increment_names[*idx] = name.clone();
+        }
+
+        let mut processes = Vec::with_capacity(equations.len());
+        for (equation, process) in equations.iter().zip(&universe.processes) {
+            let (_, metadata) = split_annotation(equation)?;
+            let terms = match process {
+                Process::Levy(levy) => levy
+                    .coefficients
+                    .iter()
+                    .zip(&levy.incrementors)
+                    .map(|(coefficient, incrementor)| {
+                        let increment_idx = incrementor.stochastic_idx();
+                        TermDescriptor {
+                            coefficient: coefficient.source().to_string(),
+                            differential: increment_idx
+                                .map_or_else(|| "dt".to_string(), |i| increment_names[i].clone()),
+                            kind: incrementor.kind(),
+                            increment_idx,
+                        }
+                    })
+                    .collect(),
+                Process::Algebraic(alg) => vec![TermDescriptor {
+                    coefficient: alg.coefficients[0].source().to_string(),
+                    differential: String::new(),
+                    kind: IncrementKind::Other,
+                    increment_idx: None,
+                }],
+            };
+
+            let functions = process.functions();
+            let variables: BTreeSet<String> =
+                functions.iter().flat_map(|f| f.variables()).collect();
+            let referenced_processes = variables
+                .iter()
+                .filter(|v| universe.process_registry.contains_key(*v))
+                .cloned()
+                .collect();
+            let parameters = variables
+                .iter()
+                .filter(|v| {
+                    *v != "t" && !v.starts_with("__") && !universe.process_registry.contains_key(*v)
+                })
+                .cloned()
+                .collect();
+            let delayed_references = functions
+                .iter()
+                .flat_map(|f| {
+                    f.lags()
+                        .iter()
+                        .map(|l| l.process.clone())
+                        .chain(f.aggregates().iter().map(|a| a.process.clone()))
+                })
+                .collect();
+
+            processes.push(ProcessDescriptor {
+                name: process.name().to_string(),
+                equation: equation.clone(),
+                algebraic: matches!(process, Process::Algebraic(_)),
+                value_kind: process.value_kind(),
+                terms,
+                referenced_processes,
+                delayed_references,
+                parameters,
+                metadata,
+            });
+        }
+        Ok(Self { processes })
+    }
+
+    /// Every process mapped to the processes it reads, currently or delayed.
+    pub fn dependency_graph(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.processes
+            .iter()
+            .map(|p| {
+                let refs = p
+                    .referenced_processes
+                    .union(&p.delayed_references)
+                    .cloned()
+                    .collect();
+                (p.name.clone(), refs)
+            })
+            .collect()
+    }
+
+    /// A cycle among algebraic processes reading each other's current value,
+    /// which no evaluation order can resolve, as the names along the cycle.
+    /// Stochastic processes break cycles since they only read the previous
+    /// step's state.
+    pub fn algebraic_cycle(&self) -> Option<Vec<String>> {
+        let algebraic: BTreeMap<&str, Vec<&str>> = self
+            .processes
+            .iter()
+            .filter(|p| p.algebraic)
+            .map(|p| {
+                let deps = p
+                    .referenced_processes
+                    .iter()
+                    .map(|s| s.as_str())
+                    .filter(|r| self.processes.iter().any(|q| q.algebraic && q.name == *r))
+                    .collect();
+                (p.name.as_str(), deps)
+            })
+            .collect();
+
+        // depth-first search; 1 = on the current path, 2 = done
+        let mut state: BTreeMap<&str, u8> = BTreeMap::new();
+        let mut path: Vec<&str> = Vec::new();
+        fn visit<'a>(
+            node: &'a str,
+            graph: &BTreeMap<&'a str, Vec<&'a str>>,
+            state: &mut BTreeMap<&'a str, u8>,
+            path: &mut Vec<&'a str>,
+        ) -> Option<Vec<String>> {
+            match state.get(node) {
+                Some(2) => return None,
+                Some(_) => {
+                    let start = path.iter().position(|n| *n == node).unwrap();
+                    let mut cycle: Vec<String> =
+                        path[start..].iter().map(|n| n.to_string()).collect();
+                    cycle.push(node.to_string());
+                    return Some(cycle);
+                }
+                None => {}
+            }
+            state.insert(node, 1);
+            path.push(node);
+            for next in &graph[node] {
+                if let Some(cycle) = visit(next, graph, state, path) {
+                    return Some(cycle);
+                }
+            }
+            path.pop();
+            state.insert(node, 2);
+            None
+        }
+        algebraic
+            .keys()
+            .find_map(|node| visit(node, &algebraic, &mut state, &mut path))
+    }
+
+    /// One row per term with columns `process`, `equation`, `algebraic`,
+    /// `integer`, `coefficient`, `differential`, `increment_kind`, `increment_idx`,
+    /// `references`, `delayed_references`, `parameters` and `metadata`, the
+    /// sets joined with `", "` and metadata as `key=value` pairs.
+    #[cfg(feature = "dataframe")]
+    pub fn to_dataframe(&self) -> PolarsResult<DataFrame> {
+        let join = |set: &BTreeSet<String>| set.iter().cloned().collect::<Vec<_>>().join(", ");
+        let mut process = Vec::new();
+        let mut equation = Vec::new();
+        let mut algebraic = Vec::new();
+        let mut integer = Vec::new();
+        let mut coefficient = Vec::new();
+        let mut differential = Vec::new();
+        let mut kind = Vec::new();
+        let mut increment_idx: Vec<Option<u32>> = Vec::new();
+        let mut references = Vec::new();
+        let mut delayed = Vec::new();
+        let mut parameters = Vec::new();
+        let mut metadata = Vec::new();
+        for p in &self.processes {
+            let meta = p
+                .metadata
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            for term in &p.terms {
+                process.push(p.name.clone());
+                equation.push(p.equation.clone());
+                algebraic.push(p.algebraic);
+                integer.push(p.value_kind == ValueKind::Integer);
+                coefficient.push(term.coefficient.clone());
+                differential.push((!p.algebraic).then(|| term.differential.clone()));
+                kind.push((!p.algebraic).then(|| kind_name(term.kind)));
+                increment_idx.push(term.increment_idx.map(|i| i as u32));
+                references.push(join(&p.referenced_processes));
+                delayed.push(join(&p.delayed_references));
+                parameters.push(join(&p.parameters));
+                metadata.push(meta.clone());
+            }
+        }
+        DataFrame::new(vec![
+            Column::new("process".into(), process),
+            Column::new("equation".into(), equation),
+            Column::new("algebraic".into(), algebraic),
+            Column::new("integer".into(), integer),
+            Column::new("coefficient".into(), coefficient),
+            Column::new("differential".into(), differential),
+            Column::new("increment_kind".into(), kind),
+            Column::new("increment_idx".into(), increment_idx),
+            Column::new("references".into(), references),
+            Column::new("delayed_references".into(), delayed),
+            Column::new("parameters".into(), parameters),
+            Column::new("metadata".into(), metadata),
+        ])
+    }
+}
@@ -1,7 +1,8 @@
 use crate::func::Function;
+use crate::proc::model::ParsedModel;
 use crate::proc::{AlgebraicProcess, LevyProcess, Process, ProcessUniverse, increment::*};
 use ordered_float::OrderedFloat;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 // Fixed nom imports
 use nom::{
@@ -49,9 +50,99 @@ fn extract_lambda(input: &str) -> Result<String, String> {
     }
 }
 
+/// Builds a custom incrementor from the text inside its parentheses (empty when
+/// none were given), its stochastic index and the simulation time grid.
+pub type IncrementorFactory = Box<
+    dyn Fn(&str, usize, &[OrderedFloat<f64>]) -> Result<Box<dyn Incrementor>, String> + Send + Sync,
+>;
+
+const BUILTIN_DIFFERENTIALS: [&str; 3] = ["dt", "dW", "dN"];
+
+/// User-defined incrementors, keyed by the differential prefix they handle
+/// (e.g. `"dM"` for terms like `(0.1) * dM1(p01=0.1,p10=0.2)`).
+#[derive(Default)]
+pub struct IncrementorRegistry {
+    factories: BTreeMap<String, IncrementorFactory>,
+}
+
+impl IncrementorRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register<F>(&mut self, prefix: &str, factory: F) -> Result<(), String>
+    where
+        F: Fn(&str, usize, &[OrderedFloat<f64>]) -> Result<Box<dyn Incrementor>, String>
+            + Send
+            + Sync
+            + 'static,
+    {
+        if !prefix.starts_with('d') || prefix.len() < 2 {
+            return Err(format!(
+                "Incrementor prefix '{}' must start with 'd' followed by a name",
+                prefix
+            ));
+        }
+        if BUILTIN_DIFFERENTIALS.iter().any(|b| prefix.starts_with(b)) {
+            return Err(format!(
+                "Incrementor prefix '{}' clashes with a built-in differential",
+                prefix
+            ));
+        }
+        self.factories.insert(prefix.to_string(), Box::new(factory));
+        Ok(())
+    }
+
+    /// Longest registered prefix matching the differential, if any.
+    fn find(&self, inc_str: &str) -> Option<&IncrementorFactory> {
+        self.factories
+            .iter()
+            .filter(|(prefix, _)| inc_str.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, factory)| factory)
+    }
+
+    fn prefixes(&self) -> Vec<&str> {
+        BUILTIN_DIFFERENTIALS
+            .iter()
+            .copied()
+            .chain(self.factories.keys().map(|k| k.as_str()))
+            .collect()
+    }
+}
+
+/// Parses `key=value` pairs separated by commas, as used in the arguments of
+/// custom incrementors.
+pub fn parse_named_args(args: &str) -> Result<HashMap<String, f64>, String> {
+    let mut parsed = HashMap::new();
+    for pair in args.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Expected 'key=value' argument, got '{}'", pair))?;
+        let value: f64 = value
+            .trim()
+            .parse()
+            .map_err(|_| format!("Argument '{}' is not a number", pair))?;
+        if parsed.insert(key.trim().to_string(), value).is_some() {
+            return Err(format!("Duplicate argument '{}'", key.trim()));
+        }
+    }
+    Ok(parsed)
+}
+
 pub fn parse_equations(
     equations: &[String],
     timesteps: Vec<OrderedFloat<f64>>,
+) -> Result<ProcessUniverse, String> {
+    parse_equations_with_registry(equations, timesteps, &IncrementorRegistry::default())
+}
+
+/// Same as [`parse_equations`], additionally resolving differentials handled by
+/// the user-defined incrementors in `incrementor_registry`.
+pub fn parse_equations_with_registry(
+    equations: &[String],
+    timesteps: Vec<OrderedFloat<f64>>,
+    incrementor_registry: &IncrementorRegistry,
 ) -> Result<ProcessUniverse, String> {
     let mut stochastic_registry: HashMap<String, usize> = HashMap::new();
     let mut processes = Vec::with_capacity(equations.len());
@@ -60,23 +151,68 @@ pub fn parse_equations(
             eq,
             timesteps.clone(),
             &mut stochastic_registry,
+            incrementor_registry,
         )?);
     }
     Ok(ProcessUniverse::new(processes, stochastic_registry))
 }
 
+/// Like [`parse_equations`], also returning a [`ParsedModel`] describing every
+/// equation for introspection.
+pub fn parse_model(
+    equations: &[String],
+    timesteps: Vec<OrderedFloat<f64>>,
+) -> Result<(ProcessUniverse, ParsedModel), String> {
+    parse_model_with_registry(equations, timesteps, &IncrementorRegistry::default())
+}
+
+/// Like [`parse_equations_with_registry`], also returning a [`ParsedModel`].
+pub fn parse_model_with_registry(
+    equations: &[String],
+    timesteps: Vec<OrderedFloat<f64>>,
+    incrementor_registry: &IncrementorRegistry,
+) -> Result<(ProcessUniverse, ParsedModel), String> {
+    let universe = parse_equations_with_registry(equations, timesteps, incrementor_registry)?;
+    let model = ParsedModel::new(equations, &universe)?;
+    Ok((universe, model))
+}
+
+/// Splits off a trailing annotation `# key=value, key=value` from an equation.
+pub fn split_annotation(equation: &str) -> Result<(&str, BTreeMap<String, String>), String> {
+    let Some((equation, annotation)) = equation.split_once('#') else {
+        return Ok((equation, BTreeMap::new()));
+    };
+    let mut metadata = BTreeMap::new();
+    for pair in annotation
+        .split(',')
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+    {
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("Expected 'key=value' annotation, got '{}'", pair))?;
+        if metadata
+            .insert(key.trim().to_string(), value.trim().to_string())
+            .is_some()
+        {
+            return Err(format!("Duplicate annotation '{}'", key.trim()));
+        }
+    }
+    Ok((equation, metadata))
+}
+
 fn parse_single_equation(
     equation: &str,
     timesteps: Vec<OrderedFloat<f64>>,
     stochastic_registry: &mut HashMap<String, usize>,
+    incrementor_registry: &IncrementorRegistry,
 ) -> Result<Process, String> {
-    let parts: Vec<&str> = equation.split('=').collect();
-    if parts.len() != 2 {
-        return Err("Missing '='".into());
-    }
-
-    let lhs = parts[0].trim();
-    let rhs = parts[1].trim();
+    let (equation, _) = split_annotation(equation)?;
+    // Only the first '=' separates the sides; later ones belong to incrementor
+    // arguments or comparisons inside coefficients.
+    let (lhs, rhs) = equation.split_once('=').ok_or("Missing '='")?;
+    let lhs = lhs.trim();
+    let rhs = rhs.trim();
     let process_name = lhs.strip_prefix('d').unwrap_or(lhs);
 
     if lhs.starts_with('d') {
@@ -97,17 +233,27 @@ fn parse_single_equation(
 
             let after_star = trimmed_after[1..].trim_start();
 
-            let (remaining, inc_str) = if after_star.starts_with("dN") {
-                let d_start = after_star.find('(').ok_or("dN missing opening bracket")?;
+            // The differential is a name optionally followed by its arguments in
+            // parentheses, e.g. `dW1`, `dN1(0.5 * cos(t))` or `dM1(p01=0.1)`.
+            let name_end = after_star
+                .find(|c: char| !(c.is_alphanumeric() || c == '_'))
+                .unwrap_or(after_star.len());
+            let after_name = &after_star[name_end..];
+            let (remaining, inc_str) = if after_name.trim_start().starts_with('(') {
+                let d_start = name_end + after_name.find('(').unwrap_or(0);
                 let (rest, _inside) = delimited(char('('), balanced_parens, char(')'))
                     .parse(&after_star[d_start..])
-                    .map_err(|_| "Unbalanced parentheses in dN intensity")?;
+                    .map_err(|_| {
+                        format!(
+                            "Unbalanced parentheses in arguments of '{}'",
+                            &after_star[..name_end]
+                        )
+                    })?;
 
                 let full_inc = &after_star[..after_star.len() - rest.len()];
                 (rest, full_inc)
             } else {
-                let end = after_star.find(' ').unwrap_or(after_star.len());
-                (&after_star[end..], &after_star[..end])
+                (after_name, &after_star[..name_end])
             };
 
             let coeff_fn = Box::new(
@@ -115,7 +261,12 @@ fn parse_single_equation(
                     .map_err(|e| format!("Math error in coefficient: {}", e))?,
             );
 
-            let incr = build_incrementor(inc_str, timesteps.clone(), stochastic_registry)?;
+            let incr = build_incrementor(
+                inc_str,
+                timesteps.clone(),
+                stochastic_registry,
+                incrementor_registry,
+            )?;
 
             coefficients.push(coeff_fn);
             incrementors.push(incr);
@@ -136,16 +287,26 @@ fn parse_single_equation(
 fn build_incrementor(
     inc_str: &str,
     timesteps: Vec<OrderedFloat<f64>>,
-    registry: &mut HashMap<String, usize>,
+    stochastic_registry: &mut HashMap<String, usize>,
+    incrementor_registry: &IncrementorRegistry,
 ) -> Result<Box<dyn Incrementor>, String> {
     if inc_str == "dt" {
         return Ok(Box::new(TimeIncrementor::new(timesteps)));
     }
 
-    let next_idx = registry.len();
-    let incrementor_idx = *registry.entry(inc_str.to_string()).or_insert(next_idx);
+    let next_idx = stochastic_registry.len();
+    let incrementor_idx = *stochastic_registry
+        .entry(inc_str.to_string())
+        .or_insert(next_idx);
 
-    if inc_str.starts_with("dW") {
+    if let Some(factory) = incrementor_registry.find(inc_str) {
+        let args = match inc_str.find('(') {
+            Some(start) => extract_lambda(&inc_str[start..])?,
+            None => String::new(),
+        };
+        factory(&args, incrementor_idx, &timesteps)
+            .map_err(|e| format!("Failed to build incrementor '{}': {}", inc_str, e))
+    } else if inc_str.starts_with("dW") {
         Ok(Box::new(WienerIncrementor::new(incrementor_idx, timesteps)))
     } else if inc_str.starts_with("dN") {
         let lambda_expr = extract_lambda(inc_str)?;
@@ -161,6 +322,10 @@ fn build_incrementor(
             timesteps,
         )))
     } else {
-        Err(format!("Unknown incrementor type: {}", inc_str))
+        Err(format!(
+            "Unknown incrementor type: {} (known differentials: {})",
+            inc_str,
+            incrementor_registry.prefixes().join(", ")
+        ))
     }
 }
@@ -1,21 +1,224 @@
-use crate::sim::simulate;
+use crate::filtration::Filtration;
+use crate::filtration::density::BinSpec;
+use crate::sim::config::{ProcessOptions, SimulationConfig};
+use crate::sim::risk::{self, Exposure};
+use crate::sim::simulate_with_config;
 use ordered_float::OrderedFloat;
 use pyo3::exceptions::{PyRuntimeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3_polars::PyDataFrame;
 use std::collections::HashMap;
 
+/// A process name or a weighted combination of processes.
+#[derive(FromPyObject)]
+enum PyExposure {
+    Process(String),
+    Combination(HashMap<String, f64>),
+}
+
+impl From<PyExposure> for Exposure {
+    fn from(exposure: PyExposure) -> Self {
+        match exposure {
+            PyExposure::Process(name) => Exposure::Process(name),
+            PyExposure::Combination(weights) => Exposure::Combination(weights),
+        }
+    }
+}
+
+/// Result of a simulation run, holding every scenario in memory.
+#[pyclass(name = "SimulationResult")]
+pub struct SimulationResult {
+    filtration: Filtration,
+}
+
+#[pymethods]
+impl SimulationResult {
+    /// Simulated paths in long format (scenario, time, process_name, value),
+    /// or with one column per process when `wide`, integer processes as Int64.
+    #[pyo3(signature = (wide = false))]
+    fn paths(&self, py: Python<'_>, wide: bool) -> PyResult<PyDataFrame> {
+        let df = py
+            .allow_threads(|| {
+                if wide {
+                    self.filtration.to_wide_lazyframe()?.collect()
+                } else {
+                    self.filtration.to_lazyframe().collect()
+                }
+            })
+            .map_err(|e| PyRuntimeError::new_err(format!("Polars collection error: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Cross-sectional statistics per time and process.
+    #[pyo3(signature = (quantiles = vec![0.05, 0.5, 0.95]))]
+    fn statistics(&self, py: Python<'_>, quantiles: Vec<f64>) -> PyResult<PyDataFrame> {
+        let df = py
+            .allow_threads(|| self.filtration.statistics(&quantiles))
+            .map_err(|e| PyValueError::new_err(format!("Failed to compute statistics: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Cross-scenario histogram of `process` at each of `times`. Equal-width
+    /// bins over `range`, or Freedman-Diaconis bins without it.
+    #[pyo3(signature = (process, times, bins = None, range = None))]
+    fn histogram(
+        &self,
+        py: Python<'_>,
+        process: &str,
+        times: Vec<f64>,
+        bins: Option<usize>,
+        range: Option<(f64, f64)>,
+    ) -> PyResult<PyDataFrame> {
+        let spec = match (range, bins) {
+            (Some((min, max)), n) => BinSpec::Fixed {
+                min,
+                max,
+                n: n.unwrap_or(50),
+            },
+            (None, None) => BinSpec::Auto,
+            (None, Some(_)) => {
+                return Err(PyValueError::new_err("bins requires range to be given"));
+            }
+        };
+        let df = py
+            .allow_threads(|| self.filtration.histogram(process, &times, spec))
+            .map_err(|e| PyValueError::new_err(format!("Failed to compute histogram: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Gaussian kernel density estimate of `process` at each of `times`.
+    #[pyo3(signature = (process, times, bandwidth = None, grid_points = 512))]
+    fn kde(
+        &self,
+        py: Python<'_>,
+        process: &str,
+        times: Vec<f64>,
+        bandwidth: Option<f64>,
+        grid_points: usize,
+    ) -> PyResult<PyDataFrame> {
+        let df = py
+            .allow_threads(|| self.filtration.kde(process, &times, bandwidth, grid_points))
+            .map_err(|e| PyValueError::new_err(format!("Failed to compute KDE: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Value-at-Risk at confidence `alpha` of a process or a weighted
+    /// combination (`{"A": 0.6, "B": 0.4}`) at each of `times`.
+    fn var(
+        &self,
+        py: Python<'_>,
+        exposure: PyExposure,
+        alpha: f64,
+        times: Vec<f64>,
+    ) -> PyResult<PyDataFrame> {
+        let exposure = Exposure::from(exposure);
+        let df = py
+            .allow_threads(|| risk::var(&self.filtration, &exposure, alpha, &times))
+            .map_err(|e| PyValueError::new_err(format!("Failed to compute VaR: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Expected shortfall at confidence `alpha`, see `var`.
+    fn cvar(
+        &self,
+        py: Python<'_>,
+        exposure: PyExposure,
+        alpha: f64,
+        times: Vec<f64>,
+    ) -> PyResult<PyDataFrame> {
+        let exposure = Exposure::from(exposure);
+        let df = py
+            .allow_threads(|| risk::cvar(&self.filtration, &exposure, alpha, &times))
+            .map_err(|e| PyValueError::new_err(format!("Failed to compute CVaR: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Probability of falling below `threshold`, see `var`.
+    fn shortfall_probability(
+        &self,
+        py: Python<'_>,
+        exposure: PyExposure,
+        threshold: f64,
+        times: Vec<f64>,
+    ) -> PyResult<PyDataFrame> {
+        let exposure = Exposure::from(exposure);
+        let df = py
+            .allow_threads(|| {
+                risk::shortfall_probability(&self.filtration, &exposure, threshold, &times)
+            })
+            .map_err(|e| {
+                PyValueError::new_err(format!("Failed to compute shortfall probability: {}", e))
+            })?;
+        Ok(PyDataFrame(df))
+    }
+
+    /// Sampled stochastic increments in long format: scenario, time,
+    /// increment_name, value. Requires `record_increments=True`.
+    fn increments(&self, py: Python<'_>) -> PyResult<PyDataFrame> {
+        let lf = self
+            .filtration
+            .increments_lazyframe()
+            .map_err(PyValueError::new_err)?;
+        let df = py
+            .allow_threads(|| lf.collect())
+            .map_err(|e| PyRuntimeError::new_err(format!("Polars collection error: {}", e)))?;
+        Ok(PyDataFrame(df))
+    }
+}
+
+/// Reads the time grid from any iterable of floats (list, tuple, numpy array)
+/// and checks that it is strictly increasing.
+fn extract_time_steps(time_steps: &Bound<'_, PyAny>) -> PyResult<Vec<OrderedFloat<f64>>> {
+    let mut steps: Vec<OrderedFloat<f64>> = Vec::new();
+    for item in time_steps.try_iter()? {
+        let t: f64 = item?.extract()?;
+        if !t.is_finite() {
+            return Err(PyValueError::new_err(format!(
+                "time_steps[{}] is not finite",
+                steps.len()
+            )));
+        }
+        if let Some(prev) = steps.last()
+            && t <= prev.0
+        {
+            return Err(PyValueError::new_err(format!(
+                "time_steps must be strictly increasing, but time_steps[{}] = {} follows {}",
+                steps.len(),
+                t,
+                prev.0
+            )));
+        }
+        steps.push(OrderedFloat(t));
+    }
+    Ok(steps)
+}
+
 #[pyfunction]
-#[pyo3(name = "simulate")]
+#[pyo3(
+    name = "simulate",
+    signature = (
+        processes_equations,
+        time_steps,
+        scenarios,
+        initial_values,
+        rng_method = "pseudo".to_string(),
+        scheme = "euler".to_string(),
+        record_increments = false,
+        linear_drift = None,
+    )
+)]
+#[allow(clippy::too_many_arguments)]
 pub fn simulate_py(
     py: Python<'_>,
     processes_equations: Vec<String>,
-    time_steps: Vec<f64>,
+    time_steps: &Bound<'_, PyAny>,
     scenarios: i32,
     initial_values: HashMap<String, f64>,
     rng_method: String,
     scheme: String,
-) -> PyResult<PyDataFrame> {
+    record_increments: bool,
+    linear_drift: Option<HashMap<String, f64>>,
+) -> PyResult<SimulationResult> {
     // Basic validation for scenario count
     if scenarios <= 0 {
         return Err(PyValueError::new_err(
@@ -23,40 +226,43 @@ pub fn simulate_py(
         ));
     }
 
-    let time_steps_ordered: Vec<OrderedFloat<f64>> =
-        time_steps.iter().copied().map(OrderedFloat).collect();
+    let time_steps_ordered = extract_time_steps(time_steps)?;
 
     // 1. Parse equations and map internal errors to Python ValueErrors
     let processes =
         crate::proc::util::parse_equations(&processes_equations, time_steps_ordered.clone())
             .map_err(|e| PyValueError::new_err(format!("Failed to parse equations: {}", e)))?;
 
+    let mut config = SimulationConfig::new(
+        processes,
+        time_steps_ordered,
+        initial_values,
+        scenarios as u64,
+    )
+    .with_scheme(&scheme)
+    .with_rng_method(&rng_method)
+    .with_record_increments(record_increments);
+    for (process, a) in linear_drift.unwrap_or_default() {
+        config = config.with_process_options(
+            &process,
+            ProcessOptions {
+                linear_drift: Some(a),
+            },
+        );
+    }
+
     // 2. Run simulation while releasing the GIL
     // We map simulation errors to PyRuntimeError
-    let df = py
-        .allow_threads(|| {
-            simulate(
-                &processes,
-                time_steps_ordered,
-                initial_values,
-                scenarios as u64,
-                &scheme,
-                &rng_method,
-            )
-        })
+    let filtration = py
+        .allow_threads(|| simulate_with_config(&config))
         .map_err(|e| PyRuntimeError::new_err(format!("Simulation failed: {}", e)))?;
 
-    // 3. Collect the LazyFrame into a DataFrame
-    // Polars errors are converted to Python-friendly messages
-    let collected_df = df
-        .collect()
-        .map_err(|e| PyRuntimeError::new_err(format!("Polars collection error: {}", e)))?;
-
-    Ok(PyDataFrame(collected_df))
+    Ok(SimulationResult { filtration })
 }
 
 #[pymodule]
 fn sde_sim_rs(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<SimulationResult>()?;
     m.add_function(wrap_pyfunction!(simulate_py, m)?)?;
     Ok(())
 }
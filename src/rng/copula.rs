@@ -0,0 +1,229 @@
+use crate::math::dist::{inverse_normal_cdf, normal_cdf};
+use crate::math::linalg::cholesky;
+use crate::rng::{BaseRng, StepCache};
+use std::sync::Arc;
+
+/// Smallest distance kept from 0 and 1 so inverse CDFs stay finite.
+const UNIFORM_EPS: f64 = 1e-15;
+
+#[inline]
+fn clamp_open(u: f64) -> f64 {
+    u.clamp(UNIFORM_EPS, 1.0 - UNIFORM_EPS)
+}
+
+/// Dependence structure imposed on the uniforms of several increments.
+///
+/// `transform` receives the step's uniforms of the coupled increments followed
+/// by [`Copula::latent_dims`] extra independent uniforms, and rewrites the
+/// leading entries in place. Outputs must lie strictly inside (0, 1).
+pub trait Copula: Send + Sync {
+    fn transform(&self, uniforms: &mut [f64]);
+    /// Additional independent uniforms consumed per step.
+    fn latent_dims(&self) -> usize {
+        0
+    }
+    /// Number of coupled increments, if the copula is of fixed dimension.
+    fn dimension(&self) -> Option<usize> {
+        None
+    }
+}
+
+/// Leaves the uniforms untouched.
+pub struct IndependenceCopula;
+
+impl Copula for IndependenceCopula {
+    fn transform(&self, _uniforms: &mut [f64]) {}
+}
+
+/// Gaussian copula with the given correlation matrix.
+pub struct GaussianCopula {
+    dim: usize,
+    cholesky: Vec<f64>,
+}
+
+impl GaussianCopula {
+    /// `correlation` is a row-major `n x n` positive definite correlation matrix.
+    pub fn new(correlation: &[f64], n: usize) -> Result<Self, String> {
+        if correlation.len() != n * n {
+            return Err(format!(
+                "Correlation matrix has {} entries, expected {}",
+                correlation.len(),
+                n * n
+            ));
+        }
+        for i in 0..n {
+            if (correlation[i * n + i] - 1.0).abs() > 1e-12 {
+                return Err(format!("Correlation matrix diagonal entry {} is not 1", i));
+            }
+            for j in 0..i {
+                if correlation[i * n + j] != correlation[j * n + i] {
+                    return Err(format!(
+                        "Correlation matrix is not symmetric at ({}, {})",
+                        i, j
+                    ));
+                }
+            }
+        }
+        let cholesky =
+            cholesky(correlation, n).ok_or("Correlation matrix is not positive definite")?;
+        Ok(Self { dim: n, cholesky })
+    }
+}
+
+impl Copula for GaussianCopula {
+    fn transform(&self, uniforms: &mut [f64]) {
+        let n = self.dim;
+        let z: Vec<f64> = uniforms[..n]
+            .iter()
+            .map(|u| inverse_normal_cdf(clamp_open(*u)))
+            .collect();
+        for (i, u) in uniforms[..n].iter_mut().enumerate() {
+            let y: f64 = (0..=i).map(|k| self.cholesky[i * n + k] * z[k]).sum();
+            *u = clamp_open(normal_cdf(y));
+        }
+    }
+
+    fn dimension(&self) -> Option<usize> {
+        Some(self.dim)
+    }
+}
+
+/// Clayton copula (lower-tail dependence), `theta > 0`, sampled by sequential
+/// conditional inversion.
+pub struct ClaytonCopula {
+    theta: f64,
+}
+
+impl ClaytonCopula {
+    pub fn new(theta: f64) -> Result<Self, String> {
+        if !(theta > 0.0 && theta.is_finite()) {
+            return Err(format!("Clayton parameter must be positive, got {}", theta));
+        }
+        Ok(Self { theta })
+    }
+}
+
+impl Copula for ClaytonCopula {
+    fn transform(&self, uniforms: &mut [f64]) {
+        let theta = self.theta;
+        // running sum of u_j^-theta - 1 over the already coupled entries
+        let mut acc = 0.0;
+        for (k, u) in uniforms.iter_mut().enumerate() {
+            let v = clamp_open(*u);
+            let out = if k == 0 {
+                v
+            } else {
+                let s = 1.0 + acc;
+                let w = v.powf(-theta / (1.0 + k as f64 * theta)) - 1.0;
+                (1.0 + s * w).powf(-1.0 / theta)
+            };
+            let out = clamp_open(out);
+            acc += out.powf(-theta) - 1.0;
+            *u = out;
+        }
+    }
+}
+
+/// Gumbel copula (upper-tail dependence), `theta >= 1`, sampled with the
+/// Marshall-Olkin construction from a positive stable latent variable.
+pub struct GumbelCopula {
+    theta: f64,
+}
+
+impl GumbelCopula {
+    pub fn new(theta: f64) -> Result<Self, String> {
+        if !(theta >= 1.0 && theta.is_finite()) {
+            return Err(format!(
+                "Gumbel parameter must be at least 1, got {}",
+                theta
+            ));
+        }
+        Ok(Self { theta })
+    }
+}
+
+impl Copula for GumbelCopula {
+    fn transform(&self, uniforms: &mut [f64]) {
+        let n = uniforms.len() - 2;
+        let alpha = 1.0 / self.theta;
+        // Kanter's representation of a stable variable with Laplace transform exp(-s^alpha)
+        let angle = std::f64::consts::PI * clamp_open(uniforms[n]);
+        let w = -clamp_open(uniforms[n + 1]).ln();
+        let v = (alpha * angle).sin() / angle.sin().powf(1.0 / alpha)
+            * (((1.0 - alpha) * angle).sin() / w).powf((1.0 - alpha) / alpha);
+        for u in uniforms[..n].iter_mut() {
+            let e = -clamp_open(*u).ln();
+            *u = clamp_open((-(e / v).powf(alpha)).exp());
+        }
+    }
+
+    fn latent_dims(&self) -> usize {
+        2
+    }
+}
+
+/// Wraps another generator and couples the uniforms of selected increments
+/// through a [`Copula`] at every time step.
+///
+/// The inner generator must provide `num_increments + copula.latent_dims()`
+/// dimensions; the latent uniforms are read from the trailing dimensions.
+pub struct CopulaRng<R: BaseRng> {
+    inner: R,
+    copula: Arc<dyn Copula>,
+    indices: Vec<usize>,
+    num_increments: usize,
+    last_step: Option<StepCache>,
+    scratch: Vec<f64>,
+}
+
+impl<R: BaseRng> CopulaRng<R> {
+    /// `indices` are the increment indices coupled by the copula, in the order
+    /// the copula expects them.
+    pub fn new(
+        inner: R,
+        copula: Arc<dyn Copula>,
+        indices: Vec<usize>,
+        num_increments: usize,
+    ) -> Self {
+        Self {
+            inner,
+            copula,
+            indices,
+            num_increments,
+            last_step: None,
+            scratch: Vec::new(),
+        }
+    }
+
+    fn refresh_cache(&mut self, time_idx: usize) {
+        let mut values: Vec<f64> = (0..self.num_increments)
+            .map(|i| self.inner.sample(time_idx, i))
+            .collect();
+        self.scratch.clear();
+        self.scratch.extend(self.indices.iter().map(|&i| values[i]));
+        let latent = self.copula.latent_dims();
+        self.scratch
+            .extend((0..latent).map(|j| self.inner.sample(time_idx, self.num_increments + j)));
+        self.copula.transform(&mut self.scratch);
+        for (&i, &u) in self.indices.iter().zip(self.scratch.iter()) {
+            values[i] = u;
+        }
+        self.last_step = Some(StepCache {
+            time_idx: Some(time_idx),
+            values,
+        });
+    }
+}
+
+impl<R: BaseRng> BaseRng for CopulaRng<R> {
+    fn sample(&mut self, time_idx: usize, increment_idx: usize) -> f64 {
+        let is_cached = self
+            .last_step
+            .as_ref()
+            .is_some_and(|c| c.time_idx == Some(time_idx));
+        if !is_cached {
+            self.refresh_cache(time_idx);
+        }
+        self.last_step.as_ref().unwrap().values[increment_idx]
+    }
+}
@@ -1,13 +1,31 @@
+pub mod copula;
+pub mod philox;
 pub mod pseudo;
+pub mod rqmc;
 pub mod sobol;
 
 /// Trait for generating random or quasi-random numbers.
-pub trait BaseRng {
+pub trait BaseRng: Send {
     fn sample(&mut self, time_idx: usize, increment_idx: usize) -> f64;
 }
 
+impl<R: BaseRng + ?Sized> BaseRng for Box<R> {
+    fn sample(&mut self, time_idx: usize, increment_idx: usize) -> f64 {
+        (**self).sample(time_idx, increment_idx)
+    }
+}
+
 /// Caches the generated random numbers for the current time step.
 struct StepCache {
     time_idx: Option<usize>,
     values: Vec<f64>,
 }
+
+/// Mixes a master seed and a scenario number into an independent seed
+/// (SplitMix64 finalizer).
+pub(crate) fn scenario_seed(seed: u64, scenario: u64) -> u64 {
+    let mut z = seed ^ scenario.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
+}
@@ -0,0 +1,66 @@
+use crate::rng::{BaseRng, scenario_seed};
+
+const PHILOX_M0: u32 = 0xD251_1F53;
+const PHILOX_M1: u32 = 0xCD9E_8D57;
+const PHILOX_W0: u32 = 0x9E37_79B9;
+const PHILOX_W1: u32 = 0xBB67_AE85;
+const PHILOX_ROUNDS: usize = 10;
+
+#[inline]
+fn mulhilo(a: u32, b: u32) -> (u32, u32) {
+    let p = a as u64 * b as u64;
+    ((p >> 32) as u32, p as u32)
+}
+
+/// Philox4x32-10 block function (Salmon et al., 2011).
+fn philox4x32(mut ctr: [u32; 4], mut key: [u32; 2]) -> [u32; 4] {
+    for round in 0..PHILOX_ROUNDS {
+        if round > 0 {
+            key[0] = key[0].wrapping_add(PHILOX_W0);
+            key[1] = key[1].wrapping_add(PHILOX_W1);
+        }
+        let (hi0, lo0) = mulhilo(PHILOX_M0, ctr[0]);
+        let (hi1, lo1) = mulhilo(PHILOX_M1, ctr[2]);
+        ctr = [hi1 ^ ctr[1] ^ key[0], lo1, hi0 ^ ctr[3] ^ key[1], lo0];
+    }
+    ctr
+}
+
+/// Counter-based generator: every uniform is a pure function of
+/// `(seed, scenario, time_idx, increment_idx)`, so draws can be made in any
+/// order, from any thread, and reproduced for a single scenario in isolation.
+pub struct PhiloxRng {
+    key: [u32; 2],
+    scenario: u64,
+}
+
+impl PhiloxRng {
+    pub fn new(seed: u64, scenario: u64) -> Self {
+        Self {
+            key: [seed as u32, (seed >> 32) as u32],
+            scenario,
+        }
+    }
+
+    /// Generator for auxiliary draws of `scenario` that are independent of
+    /// `new(seed, scenario)`, e.g. Brownian bridge refinements; every `stream`
+    /// yields a different one.
+    pub fn substream(seed: u64, scenario: u64, stream: u64) -> Self {
+        Self::new(scenario_seed(seed, stream), scenario)
+    }
+}
+
+impl BaseRng for PhiloxRng {
+    fn sample(&mut self, time_idx: usize, increment_idx: usize) -> f64 {
+        let ctr = [
+            increment_idx as u32,
+            time_idx as u32,
+            self.scenario as u32,
+            (self.scenario >> 32) as u32,
+        ];
+        let out = philox4x32(ctr, self.key);
+        let bits = ((out[0] as u64) << 32 | out[1] as u64) >> 11;
+        // midpoint of a 2^-53 grid cell, strictly inside (0, 1)
+        (bits as f64 + 0.5) * (1.0 / (1u64 << 53) as f64)
+    }
+}
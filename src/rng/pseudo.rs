@@ -19,8 +19,31 @@ impl PseudoRng {
         }
     }
 
+    /// Generator for one scenario: the ChaCha stream `scenario` under the
+    /// master `seed`, independent of how many other scenarios run and in which
+    /// order.
+    pub fn for_scenario(seed: u64, scenario: u64, num_increments: usize) -> Self {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        rng.set_stream(scenario);
+        Self {
+            last_step: None,
+            num_increments,
+            rng,
+        }
+    }
+
     fn refresh_cache(&mut self, time_idx: usize) {
-        let mut values = Vec::with_capacity(self.num_increments);
+        // every step starts at a fixed stream position, so a path resumed at a
+        // later step draws exactly what an uninterrupted run would
+        self.rng
+            .set_word_pos(2 * (time_idx as u128) * self.num_increments as u128);
+        // reuse the previous step's buffer
+        let mut values = self
+            .last_step
+            .take()
+            .map(|c| c.values)
+            .unwrap_or_else(|| Vec::with_capacity(self.num_increments));
+        values.clear();
         for _ in 0..self.num_increments {
             values.push(self.rng.random::<f64>());
         }
@@ -0,0 +1,66 @@
+use crate::rng::BaseRng;
+use crate::rng::sobol::{RandomShiftScrambler, SobolEngine};
+use std::sync::Arc;
+
+/// Sobol points shared by all replications of a randomized QMC run.
+pub struct RqmcPointSet {
+    points: Vec<Vec<f64>>,
+}
+
+impl RqmcPointSet {
+    pub fn new(dims: usize, num_points: usize) -> Self {
+        let mut engine = SobolEngine::new(dims);
+        let points = (0..num_points)
+            .map(|_| engine.next_path().expect("Sobol sequence exhausted"))
+            .collect();
+        Self { points }
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+}
+
+/// Randomized QMC generator: every replication sees the same Sobol points under
+/// its own random shift, so replications are independent and unbiased.
+pub struct RqmcRng {
+    num_increments: usize,
+    values: Vec<f64>,
+}
+
+impl RqmcRng {
+    /// `point_idx` selects the Sobol point; `replication_seed` must be shared by
+    /// all scenarios of the same replication.
+    pub fn new(
+        point_set: &Arc<RqmcPointSet>,
+        point_idx: usize,
+        replication_seed: u64,
+        num_increments: usize,
+        num_timesteps: usize,
+    ) -> Self {
+        let dims = (num_timesteps - 1) * num_increments;
+        let scrambler = RandomShiftScrambler::new(dims, replication_seed);
+        let values = scrambler.scramble(point_set.points[point_idx].clone());
+        Self {
+            num_increments,
+            values,
+        }
+    }
+}
+
+impl BaseRng for RqmcRng {
+    fn sample(&mut self, time_idx: usize, increment_idx: usize) -> f64 {
+        self.values[time_idx * self.num_increments + increment_idx]
+    }
+}
+
+/// Assigns scenarios to replications round-robin and returns the
+/// `(replication, point index)` of scenario `s_idx`.
+pub fn replication_of(s_idx: u64, replications: usize) -> (usize, usize) {
+    let r = replications as u64;
+    ((s_idx % r) as usize, (s_idx / r) as usize)
+}
@@ -1,26 +1,66 @@
-use crate::rng::BaseRng;
+use crate::rng::{BaseRng, scenario_seed};
 use rand::{Rng as RandRng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use sobol::params::JoeKuoD6;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex, OnceLock};
 
 static SOBOL_PARAMS: OnceLock<JoeKuoD6> = OnceLock::new();
 
+/// Number of leading points dropped from the sequence.
+const SOBOL_SKIP: usize = 5;
+
 /// The internal "Engine" that is shared across all scenarios.
 pub struct SobolEngine {
+    dims: usize,
     sobol_iter: Box<dyn Iterator<Item = Vec<f64>> + Send>,
+    /// index of the point `sobol_iter` yields next
+    next_idx: u64,
+    /// points generated ahead of their request, by index
+    pending: HashMap<u64, Vec<f64>>,
 }
 
 impl SobolEngine {
     pub fn new(dims: usize) -> Self {
-        let params = SOBOL_PARAMS.get_or_init(JoeKuoD6::extended);
-        let sobol_iter = sobol::Sobol::<f64>::new(dims, params).skip(5);
+        Self::starting_at(dims, 0)
+    }
+
+    /// Engine whose first point is the `first`-th point of the sequence.
+    pub fn starting_at(dims: usize, first: u64) -> Self {
         Self {
-            sobol_iter: Box::new(sobol_iter),
+            dims,
+            sobol_iter: Self::sequence(dims, first),
+            next_idx: first,
+            pending: HashMap::new(),
         }
     }
 
+    fn sequence(dims: usize, first: u64) -> Box<dyn Iterator<Item = Vec<f64>> + Send> {
+        let params = SOBOL_PARAMS.get_or_init(JoeKuoD6::extended);
+        Box::new(sobol::Sobol::<f64>::new(dims, params).skip(SOBOL_SKIP + first as usize))
+    }
+
     pub fn next_path(&mut self) -> Option<Vec<f64>> {
+        self.next_idx += 1;
+        self.sobol_iter.next()
+    }
+
+    /// The `idx`-th point of the sequence, whatever order points are requested
+    /// in. Points passed over on the way are kept until they are asked for.
+    pub fn point(&mut self, idx: u64) -> Option<Vec<f64>> {
+        if let Some(point) = self.pending.remove(&idx) {
+            return Some(point);
+        }
+        if idx < self.next_idx {
+            // already handed out once; regenerate it
+            return Self::sequence(self.dims, idx).next();
+        }
+        while self.next_idx < idx {
+            let point = self.sobol_iter.next()?;
+            self.pending.insert(self.next_idx, point);
+            self.next_idx += 1;
+        }
+        self.next_idx += 1;
         self.sobol_iter.next()
     }
 }
@@ -51,6 +91,29 @@ impl SobolRng {
             values: scrambled,
         }
     }
+
+    /// Generator for one scenario: the `scenario`-th Sobol point under a shift
+    /// derived from `(seed, scenario)`, so the result does not depend on the
+    /// order scenarios are built in.
+    pub fn for_scenario(
+        seed: u64,
+        engine: &Mutex<SobolEngine>,
+        scenario: u64,
+        num_increments: usize,
+        num_timesteps: usize,
+    ) -> Self {
+        let raw = engine
+            .lock()
+            .unwrap()
+            .point(scenario)
+            .expect("Sobol sequence exhausted");
+        let dims = (num_timesteps - 1) * num_increments;
+        let scrambler = RandomShiftScrambler::new(dims, scenario_seed(seed, scenario));
+        Self {
+            num_increments,
+            values: scrambler.scramble(raw),
+        }
+    }
 }
 
 impl BaseRng for SobolRng {
@@ -59,18 +122,18 @@ impl BaseRng for SobolRng {
     }
 }
 
-struct RandomShiftScrambler {
+pub(crate) struct RandomShiftScrambler {
     shift: Vec<f64>,
 }
 
 impl RandomShiftScrambler {
-    fn new(dims: usize, seed: u64) -> Self {
+    pub(crate) fn new(dims: usize, seed: u64) -> Self {
         let mut rng = ChaCha8Rng::seed_from_u64(seed);
         let shift = (0..dims).map(|_| rng.random::<f64>()).collect();
         Self { shift }
     }
 
-    fn scramble(&self, mut values: Vec<f64>) -> Vec<f64> {
+    pub(crate) fn scramble(&self, mut values: Vec<f64>) -> Vec<f64> {
         for (val, &s) in values.iter_mut().zip(self.shift.iter()) {
             *val = (*val + s).fract();
         }
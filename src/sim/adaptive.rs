@@ -0,0 +1,317 @@
+use crate::filtration::ScenarioFiltration;
+use crate::math::dist::inverse_normal_cdf;
+use crate::proc::increment::IncrementKind;
+use crate::proc::{Process, ProcessUniverse};
+use crate::rng::BaseRng;
+use crate::rng::philox::PhiloxRng;
+use crate::sim::Stepper;
+use crate::sim::config::{AdaptiveStepping, SimulationConfig, SimulationOrder};
+use crate::sim::milstein::{LevyAreas, milstein_derivatives};
+use ordered_float::OrderedFloat;
+
+/// Deepest dyadic refinement of an output interval, so that the nodes of the
+/// Brownian tree can be numbered within `u32`.
+const MAX_LEVEL: u32 = 30;
+/// Fraction of the tolerance below which a step is doubled.
+const GROW_BELOW: f64 = 0.25;
+
+/// Checks that `config` can be simulated with adaptive steps.
+pub(crate) fn validate(
+    config: &SimulationConfig,
+    adaptive: &AdaptiveStepping,
+) -> Result<(), String> {
+    if !(adaptive.atol >= 0.0 && adaptive.rtol >= 0.0 && adaptive.atol + adaptive.rtol > 0.0) {
+        return Err("Adaptive stepping needs non-negative tolerances, not both zero".into());
+    }
+    if !(adaptive.min_dt > 0.0 && adaptive.max_dt >= adaptive.min_dt) {
+        return Err(format!(
+            "Adaptive stepping needs 0 < min_dt <= max_dt, got {} and {}",
+            adaptive.min_dt, adaptive.max_dt
+        ));
+    }
+    if adaptive.max_steps == 0 {
+        return Err("Adaptive stepping needs max_steps > 0".into());
+    }
+    if !matches!(config.scheme.as_str(), "euler" | "milstein") {
+        return Err(format!(
+            "Adaptive stepping supports the euler and milstein schemes, not '{}'",
+            config.scheme
+        ));
+    }
+    if config.scheme == "milstein" && LevyAreas::new(config)?.dims() > 0 {
+        return Err("Adaptive stepping cannot simulate Lévy areas".into());
+    }
+    if config.order != SimulationOrder::ScenarioMajor {
+        return Err("Adaptive stepping requires scenario-major simulation order".into());
+    }
+    if !config.drift_shifts.is_empty() || config.copula.is_some() {
+        return Err("Adaptive stepping does not support drift shifts or copulas".into());
+    }
+    let universe = &config.process_universe;
+    if !universe.lags().is_empty() {
+        return Err("Adaptive stepping does not support delayed terms".into());
+    }
+    for process in &universe.processes {
+        if let Process::Levy(levy) = process
+            && let Some(incr) = levy
+                .incrementors
+                .iter()
+                .find(|i| !matches!(i.kind(), IncrementKind::Time | IncrementKind::Wiener))
+        {
+            return Err(format!(
+                "Adaptive stepping supports dt and dW terms only, but '{}' has {:?}",
+                process.name(),
+                incr
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Makes `t` and the state `x` the values expressions see.
+fn load_state(filtration: &mut ScenarioFiltration, universe: &ProcessUniverse, t: f64, x: &[f64]) {
+    filtration.cache.time = OrderedFloat(t);
+    filtration.set_context_value("t", t);
+    for (process, val) in universe.processes.iter().zip(x) {
+        filtration.set_context_value(process.name(), *val);
+    }
+}
+
+/// Integrates one scenario with adaptive steps.
+pub(crate) struct AdaptivePath<'a> {
+    settings: &'a AdaptiveStepping,
+    stepper: &'a Stepper,
+    universe: &'a ProcessUniverse,
+    seed: u64,
+}
+
+impl<'a> AdaptivePath<'a> {
+    pub(crate) fn new(
+        settings: &'a AdaptiveStepping,
+        stepper: &'a Stepper,
+        universe: &'a ProcessUniverse,
+        seed: u64,
+    ) -> Self {
+        Self {
+            settings,
+            stepper,
+            universe,
+            seed,
+        }
+    }
+
+    /// Simulates `filtration` over its whole grid and returns the number of
+    /// accepted steps.
+    ///
+    /// The increment of every Wiener driver over an output interval is drawn
+    /// from `rng` like in a fixed-step run. The interval is then split
+    /// dyadically: node `n` of the Brownian tree (the root is 1, the halves of
+    /// node `n` are `2n` and `2n + 1`) splits its increment `D` over length `h`
+    /// into `D / 2 ± sqrt(h) / 2 * Z_n`, with `Z_n` drawn from a substream keyed
+    /// by the interval and counted by `n`.
+    pub(crate) fn simulate(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        rng: &mut dyn BaseRng,
+    ) -> Result<usize, String> {
+        let num_drivers = self.universe.stochastic_registry.len();
+        let num_processes = self.universe.processes.len();
+        let times = filtration.times.clone();
+        let mut x: Vec<f64> = (0..num_processes).map(|p| filtration.get(0, p)).collect();
+        let mut root = vec![0.0; num_drivers];
+        let mut step = f64::INFINITY;
+        let mut attempts = 0;
+        let mut accepted = 0;
+
+        for k in 0..times.len() - 1 {
+            let t0 = times[k].0;
+            let span = times[k + 1].0 - t0;
+            self.interval_increments(filtration, k, rng, &mut root);
+            let mut bridge = PhiloxRng::substream(self.seed, filtration.scenario as u64, k as u64);
+
+            let level_for = |h: f64| (span / h).log2().ceil().max(0.0).min(MAX_LEVEL as f64) as u32;
+            let min_level = level_for(self.settings.max_dt);
+            let max_level = ((span / self.settings.min_dt).log2().floor().max(0.0) as u32)
+                .clamp(min_level, MAX_LEVEL);
+            let mut level = level_for(step).clamp(min_level, max_level);
+            let end = 1u64 << max_level;
+            let mut pos = 0u64;
+            while pos < end {
+                attempts += 1;
+                if attempts > self.settings.max_steps {
+                    return Err(format!(
+                        "Scenario {} exceeded {} adaptive steps at t = {}",
+                        filtration.scenario, self.settings.max_steps, t0
+                    ));
+                }
+                let stride = 1u64 << (max_level - level);
+                let node = (1u64 << level) + pos / stride;
+                let h = span / (1u64 << level) as f64;
+                let t = t0 + span * pos as f64 / end as f64;
+
+                let full = self.tree_increments(&mut bridge, &root, span, node);
+                let (left, right) = split(&mut bridge, &full, h, node);
+                let x_full = self.scheme_step(filtration, &x, t, h, &full);
+                let x_mid = self.scheme_step(filtration, &x, t, 0.5 * h, &left);
+                let x_half = self.scheme_step(filtration, &x_mid, t + 0.5 * h, 0.5 * h, &right);
+
+                let error = self.error(&x_half, &x_full);
+                if error > 1.0 && level < max_level {
+                    level += 1;
+                    continue;
+                }
+                x = x_half;
+                pos += stride;
+                accepted += 1;
+                if error < GROW_BELOW && level > min_level && pos.is_multiple_of(2 * stride) {
+                    level -= 1;
+                }
+            }
+            step = span / (1u64 << level) as f64;
+            for (p_idx, val) in x.iter().enumerate() {
+                filtration.set(k + 1, p_idx, *val);
+            }
+        }
+        Ok(accepted)
+    }
+
+    /// Draws the increment of every driver over output interval `k`.
+    fn interval_increments(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        k: usize,
+        rng: &mut dyn BaseRng,
+        root: &mut [f64],
+    ) {
+        let mut drawn = vec![false; root.len()];
+        for p_idx in &self.universe.levy_process_indices {
+            if let Process::Levy(levy) = &self.universe.processes[*p_idx] {
+                for incr in &levy.incrementors {
+                    if let Some(idx) = incr.stochastic_idx()
+                        && !drawn[idx]
+                    {
+                        root[idx] = incr.sample(k, filtration, rng);
+                        filtration.record_increment(k, idx, root[idx]);
+                        drawn[idx] = true;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Increments over tree node `node`, splitting the root increments down
+    /// the path to it.
+    fn tree_increments(
+        &self,
+        bridge: &mut PhiloxRng,
+        root: &[f64],
+        span: f64,
+        node: u64,
+    ) -> Vec<f64> {
+        let mut increments = root.to_vec();
+        let depth = 63 - node.leading_zeros();
+        let mut h = span;
+        for bit in (0..depth).rev() {
+            let parent = node >> (bit + 1);
+            let (left, right) = split(bridge, &increments, h, parent);
+            increments = if (node >> bit) & 1 == 0 { left } else { right };
+            h *= 0.5;
+        }
+        increments
+    }
+
+    /// One Euler or Milstein step of length `h` from state `x` at time `t`
+    /// with Brownian increments `dw`, indexed by stochastic index.
+    fn scheme_step(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        x: &[f64],
+        t: f64,
+        h: f64,
+        dw: &[f64],
+    ) -> Vec<f64> {
+        let universe = self.universe;
+        load_state(filtration, universe, t, x);
+        let time = OrderedFloat(t);
+        let mut next = x.to_vec();
+        for p_idx in &universe.levy_process_indices {
+            if let Process::Levy(levy) = &universe.processes[*p_idx] {
+                for (coefficient, incr) in levy.coefficients.iter().zip(&levy.incrementors) {
+                    let c = coefficient.eval(time, filtration).unwrap();
+                    next[*p_idx] += match incr.stochastic_idx() {
+                        Some(idx) => c * dw[idx],
+                        None => c * h,
+                    };
+                }
+            }
+        }
+
+        if let Some(levy_areas) = &self.stepper.levy_areas {
+            let drivers = levy_areas.drivers();
+            let m = drivers.len();
+            if m > 0 {
+                // areas are not simulated, see `validate`
+                let dw: Vec<f64> = drivers.iter().map(|d| dw[*d]).collect();
+                let derivatives = milstein_derivatives(filtration, universe, drivers, time);
+                for p_idx in &universe.levy_process_indices {
+                    let process = &universe.processes[*p_idx];
+                    let row = &derivatives[*p_idx];
+                    for (term, (_, idx)) in process.diffusion_terms().into_iter().enumerate() {
+                        let l = drivers.binary_search(&idx).unwrap();
+                        for j in 0..m {
+                            let integral = if j == l {
+                                0.5 * (dw[j] * dw[j] - h)
+                            } else {
+                                0.5 * dw[j] * dw[l]
+                            };
+                            next[*p_idx] += row[term * m + j] * integral;
+                        }
+                    }
+                }
+            }
+        }
+
+        for p_idx in &self.stepper.integer_processes {
+            next[*p_idx] = next[*p_idx].round();
+        }
+        load_state(filtration, universe, t + h, &next);
+        for p_idx in &universe.algebraic_process_indices {
+            if let Process::Algebraic(alg) = &universe.processes[*p_idx] {
+                next[*p_idx] = alg.coefficients[0]
+                    .eval(OrderedFloat(t + h), filtration)
+                    .unwrap();
+                filtration.set_context_value(alg.name.as_str(), next[*p_idx]);
+            }
+        }
+        next
+    }
+
+    /// Largest difference between the two-half-step and full-step results
+    /// of the Lévy processes, relative to the tolerance.
+    fn error(&self, x_half: &[f64], x_full: &[f64]) -> f64 {
+        self.universe
+            .levy_process_indices
+            .iter()
+            .map(|p| {
+                let scale = self.settings.atol
+                    + self.settings.rtol * x_half[*p].abs().max(x_full[*p].abs());
+                let error = (x_half[*p] - x_full[*p]).abs() / scale;
+                if error.is_nan() { f64::INFINITY } else { error }
+            })
+            .fold(0.0, f64::max)
+    }
+}
+
+/// Splits the increments of tree node `node` over length `h` into those of
+/// its two halves with a Brownian bridge.
+fn split(bridge: &mut PhiloxRng, increments: &[f64], h: f64, node: u64) -> (Vec<f64>, Vec<f64>) {
+    let half_sd = 0.5 * h.sqrt();
+    let mut left = Vec::with_capacity(increments.len());
+    let mut right = Vec::with_capacity(increments.len());
+    for (i, d) in increments.iter().enumerate() {
+        let z = inverse_normal_cdf(bridge.sample(node as usize, i));
+        left.push(0.5 * d + half_sd * z);
+        right.push(0.5 * d - half_sd * z);
+    }
+    (left, right)
+}
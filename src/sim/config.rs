@@ -0,0 +1,280 @@
+use crate::proc::{ProcessUniverse, ValueKind};
+use crate::rng::copula::Copula;
+use ordered_float::OrderedFloat;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+/// Order in which the simulation loops over scenarios and time steps.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SimulationOrder {
+    /// Every scenario is simulated over the whole time grid on its own, with
+    /// scenarios running in parallel.
+    #[default]
+    ScenarioMajor,
+    /// All scenarios are advanced together one time step at a time. This is
+    /// required for mean-field terms (`mean(X)`, `std(X)`, `quantile(X, q)`),
+    /// which are evaluated across scenarios at the start of every step.
+    TimeMajor,
+}
+
+/// Treatment of the Lévy areas `∫∫ dW_j dW_l - ∫∫ dW_l dW_j` in the Milstein
+/// scheme.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LevyArea {
+    /// Areas are dropped. Exact for commutative noise, otherwise Milstein
+    /// falls back to strong order 0.5.
+    #[default]
+    Ignore,
+    /// Kloeden-Platen truncated Fourier expansion with the given number of
+    /// terms, or with a number chosen from the smallest time step.
+    Fourier(Option<usize>),
+}
+
+/// Error-controlled step sizes within the output grid, see
+/// [`SimulationConfig::with_adaptive_stepping`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AdaptiveStepping {
+    /// Absolute tolerance of the local error estimate.
+    pub atol: f64,
+    /// Relative tolerance of the local error estimate.
+    pub rtol: f64,
+    /// Smallest step; the error is accepted as is once reached.
+    pub min_dt: f64,
+    /// Largest step, in addition to the spacing of the output grid.
+    pub max_dt: f64,
+    /// Steps, accepted or rejected, after which a scenario fails.
+    pub max_steps: usize,
+}
+
+impl Default for AdaptiveStepping {
+    fn default() -> Self {
+        Self {
+            atol: 1e-3,
+            rtol: 1e-3,
+            min_dt: 1e-6,
+            max_dt: f64::INFINITY,
+            max_steps: 1_000_000,
+        }
+    }
+}
+
+/// Cross-scenario correction of the sampled Wiener increments, see
+/// [`SimulationConfig::with_moment_matching`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MomentMatching {
+    /// Every driver is shifted and scaled to zero mean and variance `dt`.
+    MeanVariance,
+    /// The drivers are jointly whitened to zero mean and covariance `dt * I`.
+    Covariance,
+}
+
+/// Per-process settings of the numerical schemes, see
+/// [`SimulationConfig::with_process_options`].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct ProcessOptions {
+    /// Coefficient `a` of the linear part `a * X` of the drift, solved exactly
+    /// by the `"exp-euler"` scheme while the rest of the drift, `drift - a * X`,
+    /// is stepped explicitly.
+    pub linear_drift: Option<f64>,
+}
+
+/// Everything needed to run a simulation, configured builder-style:
+///
+/// ```text
+/// let config = SimulationConfig::new(universe, timesteps, initial_values, 1_000)
+///     .with_scheme("runge-kutta")
+///     .with_order(SimulationOrder::TimeMajor);
+/// let filtration = simulate_with_config(&config)?;
+/// ```
+#[derive(Clone)]
+pub struct SimulationConfig {
+    pub process_universe: ProcessUniverse,
+    pub timesteps: Vec<OrderedFloat<f64>>,
+    pub initial_values: HashMap<String, f64>,
+    pub num_scenarios: u64,
+    pub scheme: String,
+    pub rng_method: String,
+    pub order: SimulationOrder,
+    /// Keep every sampled stochastic increment in the resulting filtration.
+    pub record_increments: bool,
+    /// Number of independently randomized replications the scenarios are split
+    /// into. With `rng_method = "sobol"` each replication gets its own random
+    /// shift of a common Sobol point set (randomized QMC).
+    pub replications: Option<usize>,
+    /// Copula coupling the uniforms of the named increments (e.g. `"dW1"`,
+    /// `"dN1"`), in the order the copula expects them.
+    pub copula: Option<(Vec<String>, Arc<dyn Copula>)>,
+    /// Importance sampling drift shifts `(increment name, theta expression)`
+    /// applied to Wiener drivers.
+    pub drift_shifts: Vec<(String, String)>,
+    /// Master seed. Every scenario draws from its own substream derived from
+    /// this seed and its scenario number, so results do not depend on thread
+    /// count or scheduling. A random seed is drawn from OS entropy when unset,
+    /// which needs the `os-rng` feature.
+    pub seed: Option<u64>,
+    /// Number of the first simulated scenario. Scenario `first_scenario + k`
+    /// of a run reproduces scenario `first_scenario + k` of any other run with
+    /// the same seed, e.g. to rerun a single path of a large batch.
+    pub first_scenario: u64,
+    /// Lévy area approximation of the Milstein scheme.
+    pub levy_area: LevyArea,
+    /// Whether the noise is commutative, letting Milstein skip the Lévy areas.
+    /// Checked numerically at the initial state when unset.
+    pub commutative_noise: Option<bool>,
+    /// Value kinds overriding those inferred from the equations, by process
+    /// name.
+    pub value_kinds: HashMap<String, ValueKind>,
+    /// Adaptive stepping within every grid interval, Euler and Milstein only.
+    /// Results are still reported on the time grid.
+    pub adaptive: Option<AdaptiveStepping>,
+    /// Scheme settings by process name.
+    pub process_options: HashMap<String, ProcessOptions>,
+    /// Moment matching of the Wiener increments across scenarios, off when
+    /// unset.
+    pub moment_matching: Option<MomentMatching>,
+}
+
+impl SimulationConfig {
+    pub fn new(
+        process_universe: ProcessUniverse,
+        timesteps: Vec<OrderedFloat<f64>>,
+        initial_values: HashMap<String, f64>,
+        num_scenarios: u64,
+    ) -> Self {
+        Self {
+            process_universe,
+            timesteps,
+            initial_values,
+            num_scenarios,
+            scheme: "euler".to_string(),
+            rng_method: "pseudo".to_string(),
+            order: SimulationOrder::default(),
+            record_increments: false,
+            replications: None,
+            copula: None,
+            drift_shifts: Vec::new(),
+            seed: None,
+            first_scenario: 0,
+            levy_area: LevyArea::default(),
+            commutative_noise: None,
+            value_kinds: HashMap::new(),
+            adaptive: None,
+            process_options: HashMap::new(),
+            moment_matching: None,
+        }
+    }
+
+    pub fn with_scheme(mut self, scheme: &str) -> Self {
+        self.scheme = scheme.to_string();
+        self
+    }
+
+    pub fn with_levy_area(mut self, levy_area: LevyArea) -> Self {
+        self.levy_area = levy_area;
+        self
+    }
+
+    pub fn with_commutative_noise(mut self, commutative_noise: bool) -> Self {
+        self.commutative_noise = Some(commutative_noise);
+        self
+    }
+
+    /// `"pseudo"` (ChaCha streams), `"philox"` (counter-based) or `"sobol"`.
+    pub fn with_rng_method(mut self, rng_method: &str) -> Self {
+        self.rng_method = rng_method.to_string();
+        self
+    }
+
+    pub fn with_order(mut self, order: SimulationOrder) -> Self {
+        self.order = order;
+        self
+    }
+
+    pub fn with_record_increments(mut self, record_increments: bool) -> Self {
+        self.record_increments = record_increments;
+        self
+    }
+
+    pub fn with_replications(mut self, replications: usize) -> Self {
+        self.replications = Some(replications);
+        self
+    }
+
+    pub fn with_copula<C: Copula + 'static>(mut self, increments: &[&str], copula: C) -> Self {
+        let names = increments.iter().map(|s| s.to_string()).collect();
+        self.copula = Some((names, Arc::new(copula)));
+        self
+    }
+
+    /// Simulates under a measure where the Wiener driver `increment` (e.g.
+    /// `"dW1"`) has extra drift `theta`, an expression of `t` and the processes,
+    /// tracking the Girsanov likelihood ratio as scenario weights.
+    pub fn with_drift_shift(mut self, increment: &str, theta: &str) -> Self {
+        self.drift_shifts
+            .push((increment.to_string(), theta.to_string()));
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn with_first_scenario(mut self, first_scenario: u64) -> Self {
+        self.first_scenario = first_scenario;
+        self
+    }
+
+    /// Steps every scenario with its own step sizes, halving a step while the
+    /// step-doubling error estimate (one full step against two half steps on
+    /// the same Brownian path) exceeds `atol + rtol * |X|` and doubling it when
+    /// well below. Steps subdivide the intervals of `timesteps` dyadically, so
+    /// values are reported exactly on that grid; within an interval the
+    /// Brownian path is refined by Brownian bridges drawn from a counter-based
+    /// substream, so runs stay reproducible and the increment over every
+    /// interval is the one a fixed-step run draws.
+    ///
+    /// Supports `dt` and `dW` terms without delayed or mean-field terms,
+    /// drift shifts or copulas.
+    pub fn with_adaptive_stepping(mut self, adaptive: AdaptiveStepping) -> Self {
+        self.adaptive = Some(adaptive);
+        self
+    }
+
+    /// Corrects the Wiener increments of every step across scenarios before
+    /// the scheme uses them: the raw draws of each driver are shifted to an
+    /// exact sample mean of zero and, by population moments, scaled to an
+    /// exact variance `dt` (or whitened to covariance `dt * I` across drivers).
+    /// This removes sampling noise from the low moments at small scenario
+    /// counts at the cost of scenarios no longer being independent.
+    ///
+    /// Needs time-major order and at least two scenarios; cannot be combined
+    /// with adaptive stepping, replications or resuming, and covariance
+    /// matching not with a copula.
+    pub fn with_moment_matching(mut self, moment_matching: MomentMatching) -> Self {
+        self.moment_matching = Some(moment_matching);
+        self
+    }
+
+    /// Treats `process` as continuous or integer-valued regardless of what its
+    /// equation implies. An integer process needs an integer initial value.
+    pub fn with_value_kind(mut self, process: &str, kind: ValueKind) -> Self {
+        self.value_kinds.insert(process.to_string(), kind);
+        self
+    }
+
+    /// Sets the scheme settings of `process`, replacing earlier ones. With
+    /// `linear_drift: Some(a)` the `"exp-euler"` scheme steps `process` as
+    ///
+    /// ```text
+    /// X' = e^(a dt) X + (e^(a dt) - 1) / a * f(X) + sqrt((e^(2 a dt) - 1) / (2 a dt)) * g(X) dW
+    /// ```
+    ///
+    /// with `f = drift - a * X`, which stays stable and exact for linear
+    /// drifts with additive noise however large `-a * dt` is. Other terms are
+    /// added as in Euler, and processes without a linear drift step with Euler.
+    pub fn with_process_options(mut self, process: &str, options: ProcessOptions) -> Self {
+        self.process_options.insert(process.to_string(), options);
+        self
+    }
+}
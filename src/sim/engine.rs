@@ -0,0 +1,116 @@
+use crate::filtration::Filtration;
+use crate::func::Aggregate;
+use crate::sim::config::SimulationConfig;
+use crate::sim::{
+    RngFactory, ScenarioState, Stepper, assign_replications, new_states, run_states, validate,
+};
+use rayon::prelude::*;
+use std::collections::{BTreeSet, HashMap};
+
+/// Reusable simulation context for running the same system many times, e.g.
+/// inside a calibration loop.
+///
+/// Equations are parsed and every buffer is allocated once, in [`new`]. Each
+/// [`run`] then only resets the paths, binds the parameter values into the
+/// already compiled expressions, reseeds the random sources and simulates.
+///
+/// Parameters are the free variables of the equations, anything that is not a
+/// process or `t`, e.g. `kappa` and `sigma` in
+/// `dX = (kappa * (1.0 - X)) * dt + (sigma) * dW1`.
+///
+/// [`new`]: SimulationEngine::new
+/// [`run`]: SimulationEngine::run
+pub struct SimulationEngine {
+    config: SimulationConfig,
+    aggregates: Vec<Aggregate>,
+    parameters: BTreeSet<String>,
+    rng_factory: RngFactory,
+    stepper: Stepper,
+    states: Vec<ScenarioState>,
+    /// First step of every scenario, always 0.
+    starts: Vec<usize>,
+    filtration: Filtration,
+}
+
+impl SimulationEngine {
+    pub fn new(config: SimulationConfig) -> Result<Self, String> {
+        let aggregates = validate(&config)?;
+        if config.adaptive.is_some() {
+            return Err("SimulationEngine does not support adaptive stepping".into());
+        }
+        let parameters = config.process_universe.parameters();
+        let stepper = Stepper::new(&config)?;
+        let rng_factory = RngFactory::new(&config, 0, stepper.auxiliary_dims())?;
+        let states = new_states(&config, &rng_factory);
+        let scenario_filtrations: Vec<_> = states.iter().map(|(f, _, _)| f).collect();
+        let mut filtration = Filtration::with_layout(&scenario_filtrations);
+        filtration.value_kinds = stepper.value_kinds.clone();
+        assign_replications(&config, &mut filtration);
+        let starts = vec![0; states.len()];
+        Ok(Self {
+            config,
+            aggregates,
+            parameters,
+            rng_factory,
+            stepper,
+            states,
+            starts,
+            filtration,
+        })
+    }
+
+    /// Names of the parameters every run must provide.
+    pub fn parameters(&self) -> impl Iterator<Item = &str> {
+        self.parameters.iter().map(|p| p.as_str())
+    }
+
+    /// Result of the latest run.
+    pub fn filtration(&self) -> &Filtration {
+        &self.filtration
+    }
+
+    /// Simulates all scenarios with the given parameter values and seed. Runs
+    /// with identical parameters and seed produce identical paths.
+    pub fn run(&mut self, params: &HashMap<String, f64>, seed: u64) -> Result<&Filtration, String> {
+        if let Some(missing) = self.parameters.iter().find(|p| !params.contains_key(*p)) {
+            return Err(format!("Missing value for parameter '{}'", missing));
+        }
+        if let Some(unknown) = params.keys().find(|p| !self.parameters.contains(*p)) {
+            return Err(format!(
+                "Unknown parameter '{}' (expected: {})",
+                unknown,
+                self.parameters
+                    .iter()
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        self.rng_factory.reseed(seed);
+        let config = &self.config;
+        let rng_factory = &self.rng_factory;
+        self.states
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(s_idx, (filtration, _, rng))| {
+                filtration.reset(&config.initial_values);
+                for (name, val) in params.iter() {
+                    filtration.set_context_value(name, *val);
+                }
+                *rng = rng_factory.build(config.first_scenario + s_idx as u64);
+            });
+
+        run_states(
+            &self.config,
+            &self.stepper,
+            &mut self.states,
+            &self.starts,
+            &self.aggregates,
+        );
+        for (s_idx, (f, _, _)) in self.states.iter().enumerate() {
+            self.filtration.store_scenario(s_idx, f);
+        }
+        Ok(&self.filtration)
+    }
+}
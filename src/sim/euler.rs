@@ -20,7 +20,12 @@ pub fn euler_iteration(
                 let c = levy.coefficients[inc_idx]
                     .eval(current_time, filtration)
                     .unwrap();
-                let x = levy.incrementors[inc_idx].sample(t_idx, filtration, rng);
+                let incrementor = &levy.incrementors[inc_idx];
+                let mut x = incrementor.sample(t_idx, filtration, rng);
+                if let Some(idx) = incrementor.stochastic_idx() {
+                    x = filtration.shift_increment(idx, x);
+                    filtration.record_increment(t_idx, idx, x);
+                }
                 val += c * x;
             }
             filtration.set(t_idx + 1, *p_idx, val);
@@ -0,0 +1,91 @@
+use crate::filtration::ScenarioFiltration;
+use crate::proc::increment::IncrementKind;
+use crate::proc::{Process, ProcessUniverse};
+use crate::rng::BaseRng;
+use crate::sim::config::SimulationConfig;
+
+/// Linear drift coefficient of every process, by process index.
+pub(crate) fn linear_drifts(config: &SimulationConfig) -> Result<Vec<Option<f64>>, String> {
+    let universe = &config.process_universe;
+    let mut drifts = vec![None; universe.processes.len()];
+    for (name, options) in &config.process_options {
+        let Some(p_idx) = universe.process_registry.get(name) else {
+            return Err(format!(
+                "Process options given for unknown process '{}'",
+                name
+            ));
+        };
+        if let Some(a) = options.linear_drift {
+            if !matches!(universe.processes[*p_idx], Process::Levy(_)) {
+                return Err(format!(
+                    "Algebraic process '{}' cannot have a linear drift",
+                    name
+                ));
+            }
+            if !a.is_finite() {
+                return Err(format!(
+                    "Linear drift of '{}' must be finite, got {}",
+                    name, a
+                ));
+            }
+            drifts[*p_idx] = Some(a);
+        }
+    }
+    Ok(drifts)
+}
+
+/// `(e^z - 1) / z`, and 1 at `z = 0`.
+fn phi(z: f64) -> f64 {
+    if z == 0.0 { 1.0 } else { z.exp_m1() / z }
+}
+
+/// Split-step exponential Euler: processes with a linear drift coefficient `a`
+/// in `linear_drifts` solve `dX = a X dt` exactly and step the remaining
+/// drift and the diffusion with the variance of the exact stochastic
+/// convolution; all other processes take an Euler step.
+pub fn exp_euler_iteration(
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    t_idx: usize,
+    rng: &mut dyn BaseRng,
+    linear_drifts: &[Option<f64>],
+) {
+    let current_time = filtration.times[t_idx];
+    let next_time = filtration.times[t_idx + 1];
+    let dt = (next_time - current_time).into_inner();
+
+    for p_idx in &process_universe.levy_process_indices {
+        if let Process::Levy(levy) = &process_universe.processes[*p_idx] {
+            // e^(a dt) x + (e^(a dt) - 1) / a * (b(x) - a x) = x + phi(a dt) b(x) dt
+            let (drift_factor, diffusion_factor) = match linear_drifts[*p_idx] {
+                Some(a) => (phi(a * dt), phi(2.0 * a * dt).sqrt()),
+                None => (1.0, 1.0),
+            };
+            let mut val = filtration.get(t_idx, *p_idx);
+            for inc_idx in 0..levy.incrementors.len() {
+                let c = levy.coefficients[inc_idx]
+                    .eval(current_time, filtration)
+                    .unwrap();
+                let incrementor = &levy.incrementors[inc_idx];
+                let mut dx = incrementor.sample(t_idx, filtration, rng);
+                if let Some(idx) = incrementor.stochastic_idx() {
+                    dx = filtration.shift_increment(idx, dx);
+                    filtration.record_increment(t_idx, idx, dx);
+                }
+                val += match incrementor.kind() {
+                    IncrementKind::Time => drift_factor * c * dx,
+                    IncrementKind::Wiener => diffusion_factor * c * dx,
+                    _ => c * dx,
+                };
+            }
+            filtration.set(t_idx + 1, *p_idx, val);
+        }
+    }
+
+    for p_idx in &process_universe.algebraic_process_indices {
+        if let Process::Algebraic(alg) = &process_universe.processes[*p_idx] {
+            let val = alg.coefficients[0].eval(next_time, filtration).unwrap();
+            filtration.set(t_idx + 1, *p_idx, val);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use crate::filtration::ScenarioFiltration;
+use crate::func::Function;
+use crate::proc::Process;
+use crate::proc::increment::{IncrementKind, Incrementor};
+use crate::rng::BaseRng;
+use crate::sim::config::SimulationConfig;
+use crate::sim::resolve_increment;
+
+/// Girsanov drift change applied to Wiener drivers.
+///
+/// Under the sampling measure Q each shifted driver is `dW = dW_Q + theta dt`,
+/// where `dW_Q` is the sampled Brownian increment. Every scenario then carries
+/// the likelihood ratio `dP/dQ = exp(-∫ theta dW_Q - 0.5 ∫ theta^2 dt)`.
+pub(crate) struct DriftShifts {
+    shifts: Vec<DriftShift>,
+}
+
+struct DriftShift {
+    idx: usize,
+    theta: Function,
+    /// An incrementor of the shifted driver, used to read the step's `dW_Q`.
+    driver: Box<dyn Incrementor>,
+}
+
+impl DriftShifts {
+    pub(crate) fn new(config: &SimulationConfig) -> Result<Option<Self>, String> {
+        if config.drift_shifts.is_empty() {
+            return Ok(None);
+        }
+        let universe = &config.process_universe;
+        let mut shifts: Vec<DriftShift> = Vec::with_capacity(config.drift_shifts.len());
+        for (name, theta) in &config.drift_shifts {
+            let idx = resolve_increment(universe, name)?;
+            if shifts.iter().any(|s| s.idx == idx) {
+                return Err(format!("Drift shift for '{}' given twice", name));
+            }
+            let driver = universe
+                .processes
+                .iter()
+                .filter_map(|p| match p {
+                    Process::Levy(levy) => Some(levy.incrementors.iter()),
+                    Process::Algebraic(_) => None,
+                })
+                .flatten()
+                .find(|i| i.stochastic_idx() == Some(idx))
+                .ok_or_else(|| format!("Increment '{}' is not used by any process", name))?;
+            if driver.kind() != IncrementKind::Wiener {
+                return Err(format!(
+                    "Drift shifts only apply to Wiener drivers, '{}' is not one",
+                    name
+                ));
+            }
+            let theta = Function::new(theta)
+                .map_err(|e| format!("Math error in drift shift for '{}': {}", name, e))?;
+            shifts.push(DriftShift {
+                idx,
+                theta,
+                driver: driver.clone_box(),
+            });
+        }
+        Ok(Some(Self { shifts }))
+    }
+
+    /// Sets the shifts of step `t_idx` from the step-start state and accumulates
+    /// the step's contribution to the likelihood ratio.
+    pub(crate) fn apply(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        t_idx: usize,
+        rng: &mut dyn BaseRng,
+    ) {
+        let t = filtration.times[t_idx];
+        let dt = (filtration.times[t_idx + 1] - t).into_inner();
+        for shift in &self.shifts {
+            let theta = shift
+                .theta
+                .eval(t, filtration)
+                .expect("Failed to evaluate drift shift");
+            let dw = shift.driver.sample(t_idx, filtration, rng);
+            filtration.set_increment_shift(shift.idx, theta * dt);
+            filtration.add_log_weight(-theta * dw - 0.5 * theta * theta * dt);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use crate::math::linalg::cholesky;
+use crate::proc::Process;
+use crate::proc::increment::{IncrementKind, Incrementor};
+use crate::sim::ScenarioState;
+use crate::sim::config::{MomentMatching, SimulationConfig, SimulationOrder};
+use rayon::prelude::*;
+
+/// Moment matching of the Wiener increments across the scenarios of a step.
+pub(crate) struct MomentMatcher {
+    mode: MomentMatching,
+    /// An incrementor of every Wiener driver, ordered by stochastic index,
+    /// used to draw the step's raw increments.
+    drivers: Vec<Box<dyn Incrementor>>,
+}
+
+impl MomentMatcher {
+    pub(crate) fn new(config: &SimulationConfig) -> Result<Option<Self>, String> {
+        let Some(mode) = config.moment_matching else {
+            return Ok(None);
+        };
+        if config.adaptive.is_some() {
+            return Err("Moment matching cannot be combined with adaptive stepping".into());
+        }
+        if config.order != SimulationOrder::TimeMajor {
+            return Err("Moment matching requires time-major simulation order".into());
+        }
+        if config.replications.is_some() {
+            return Err("Moment matching cannot be combined with replications".into());
+        }
+        if mode == MomentMatching::Covariance && config.copula.is_some() {
+            return Err("Covariance matching cannot be combined with a copula".into());
+        }
+
+        let mut drivers: Vec<Box<dyn Incrementor>> = Vec::new();
+        for process in &config.process_universe.processes {
+            if let Process::Levy(levy) = process {
+                for incr in &levy.incrementors {
+                    if incr.kind() == IncrementKind::Wiener
+                        && !drivers
+                            .iter()
+                            .any(|d| d.stochastic_idx() == incr.stochastic_idx())
+                    {
+                        drivers.push(incr.clone_box());
+                    }
+                }
+            }
+        }
+        drivers.sort_by_key(|d| d.stochastic_idx());
+        let min_scenarios = match mode {
+            MomentMatching::MeanVariance => 2,
+            MomentMatching::Covariance => drivers.len() as u64 + 1,
+        };
+        if config.num_scenarios < min_scenarios {
+            return Err(format!(
+                "Moment matching needs at least {} scenarios, got {}",
+                min_scenarios, config.num_scenarios
+            ));
+        }
+        Ok(Some(Self { mode, drivers }))
+    }
+
+    /// Draws the Wiener increments of step `t_idx` for every scenario stepping
+    /// it and stores their moment-matched replacements in the scenarios.
+    pub(crate) fn apply(&self, states: &mut [ScenarioState], starts: &[usize], t_idx: usize) {
+        let m = self.drivers.len();
+        let Some((filtration, _, _)) = states.first() else {
+            return;
+        };
+        if m == 0 {
+            return;
+        }
+        let dt = (filtration.times[t_idx + 1] - filtration.times[t_idx]).into_inner();
+
+        // raw increments of the scenarios stepping `t_idx`
+        let mut draws: Vec<Option<Vec<f64>>> = states
+            .par_iter_mut()
+            .zip(starts)
+            .map(|((filtration, _, rng), start)| {
+                (*start <= t_idx).then(|| {
+                    self.drivers
+                        .iter()
+                        .map(|d| d.sample(t_idx, filtration, rng.as_mut()))
+                        .collect()
+                })
+            })
+            .collect();
+        let n = draws.iter().flatten().count() as f64;
+        for k in 0..m {
+            let mean = draws.iter().flatten().map(|d| d[k]).sum::<f64>() / n;
+            for d in draws.iter_mut().flatten() {
+                d[k] -= mean;
+            }
+        }
+
+        let mut covariance = vec![0.0; m * m];
+        for d in draws.iter().flatten() {
+            for i in 0..m {
+                for j in 0..=i {
+                    covariance[i * m + j] += d[i] * d[j] / n;
+                }
+            }
+        }
+        for i in 0..m {
+            for j in 0..i {
+                covariance[j * m + i] = covariance[i * m + j];
+            }
+        }
+        // a degenerate sample falls back to matching the variances only
+        let whitening = match self.mode {
+            MomentMatching::Covariance => cholesky(&covariance, m),
+            MomentMatching::MeanVariance => None,
+        };
+        let sqrt_dt = dt.sqrt();
+        for d in draws.iter_mut().flatten() {
+            match &whitening {
+                // forward substitution L z = d gives covariance I
+                Some(l) => {
+                    for i in 0..m {
+                        let sum: f64 = (0..i).map(|j| l[i * m + j] * d[j]).sum();
+                        d[i] = (d[i] - sum) / l[i * m + i];
+                    }
+                }
+                None => {
+                    for (k, val) in d.iter_mut().enumerate() {
+                        let variance = covariance[k * m + k];
+                        if variance > 0.0 {
+                            *val /= variance.sqrt();
+                        }
+                    }
+                }
+            }
+            for val in d.iter_mut() {
+                *val *= sqrt_dt;
+            }
+        }
+
+        let indices: Vec<usize> = self
+            .drivers
+            .iter()
+            .map(|d| d.stochastic_idx().unwrap())
+            .collect();
+        states
+            .par_iter_mut()
+            .zip(draws)
+            .for_each(|((filtration, _, _), d)| {
+                if let Some(d) = d {
+                    let matched: Vec<(usize, f64)> = indices.iter().copied().zip(d).collect();
+                    filtration.set_matched_increments(t_idx, &matched);
+                }
+            });
+    }
+}
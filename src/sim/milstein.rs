@@ -0,0 +1,345 @@
+use crate::filtration::ScenarioFiltration;
+use crate::func::Function;
+use crate::math::dist::inverse_normal_cdf;
+use crate::proc::{Process, ProcessUniverse};
+use crate::rng::BaseRng;
+use crate::sim::config::{LevyArea, SimulationConfig};
+use ordered_float::OrderedFloat;
+use std::f64::consts::PI;
+
+/// Relative tolerance of the numerical commutativity check.
+const COMMUTATIVITY_TOL: f64 = 1e-6;
+
+/// How the Milstein scheme handles the Lévy areas of a step.
+///
+/// With commutative noise (`L^j b_l = L^l b_j` for every process) the areas
+/// cancel and are skipped; otherwise they are either dropped, leaving strong
+/// order 0.5, or approximated by the Kloeden-Platen truncated Fourier expansion
+/// with `terms` terms, which keeps strong order 1.0.
+pub struct LevyAreas {
+    /// Stochastic indices of the Wiener drivers, sorted.
+    drivers: Vec<usize>,
+    /// Fourier terms per area; 0 when areas are not simulated.
+    terms: usize,
+    /// First uniform dimension consumed, right after the stochastic drivers.
+    first_dim: usize,
+}
+
+impl LevyAreas {
+    pub fn new(config: &SimulationConfig) -> Result<Self, String> {
+        let universe = &config.process_universe;
+        let mut drivers: Vec<usize> = universe
+            .processes
+            .iter()
+            .flat_map(|p| p.diffusion_terms())
+            .map(|(_, idx)| idx)
+            .collect();
+        drivers.sort_unstable();
+        drivers.dedup();
+
+        let terms = match config.levy_area {
+            LevyArea::Ignore => 0,
+            _ if drivers.len() < 2 => 0,
+            LevyArea::Fourier(terms) => {
+                let commutative = match config.commutative_noise {
+                    Some(commutative) => commutative,
+                    None => is_commutative(config, &drivers)?,
+                };
+                match (commutative, terms) {
+                    (true, _) => 0,
+                    (false, Some(0)) => {
+                        return Err("Lévy area expansion needs at least one term".into());
+                    }
+                    (false, Some(terms)) => terms,
+                    (false, None) => default_terms(&config.timesteps),
+                }
+            }
+        };
+        Ok(Self {
+            drivers,
+            terms,
+            first_dim: universe.stochastic_registry.len(),
+        })
+    }
+
+    /// Stochastic indices of the Wiener drivers, sorted.
+    pub(crate) fn drivers(&self) -> &[usize] {
+        &self.drivers
+    }
+
+    /// Uniforms drawn per step on top of the stochastic drivers.
+    pub fn dims(&self) -> usize {
+        if self.terms == 0 {
+            0
+        } else {
+            self.drivers.len() * (2 * self.terms + 1)
+        }
+    }
+
+    /// Iterated Itô integrals `I_(j,l)` of the step for every pair of drivers,
+    /// row-major over `drivers`, given the step's Brownian increments `dw`
+    /// (indexed like `drivers`).
+    fn iterated_integrals(
+        &self,
+        t_idx: usize,
+        rng: &mut dyn BaseRng,
+        dw: &[f64],
+        dt: f64,
+    ) -> Vec<f64> {
+        let m = self.drivers.len();
+        let mut integrals = vec![0.0; m * m];
+        for j in 0..m {
+            integrals[j * m + j] = 0.5 * (dw[j] * dw[j] - dt);
+            for l in 0..j {
+                integrals[j * m + l] = 0.5 * dw[j] * dw[l];
+                integrals[l * m + j] = 0.5 * dw[j] * dw[l];
+            }
+        }
+        if self.terms == 0 {
+            return integrals;
+        }
+
+        // per driver: zeta_1..zeta_p, eta_1..eta_p, mu
+        let p = self.terms;
+        let per_driver = 2 * p + 1;
+        let normals: Vec<f64> = (0..self.dims())
+            .map(|k| {
+                let u = rng.sample(t_idx, self.first_dim + k);
+                inverse_normal_cdf(u.clamp(1e-15, 1.0 - 1e-15))
+            })
+            .collect();
+        let zeta = |j: usize, r: usize| normals[j * per_driver + r];
+        let eta = |j: usize, r: usize| normals[j * per_driver + p + r];
+        let mu = |j: usize| normals[j * per_driver + 2 * p];
+
+        let sqrt_dt = dt.sqrt();
+        let xi: Vec<f64> = dw.iter().map(|w| w / sqrt_dt).collect();
+        let tail: f64 = (1..=p).map(|r| 1.0 / (r * r) as f64).sum();
+        let rho = 1.0 / 12.0 - tail / (2.0 * PI * PI);
+        let sqrt2 = std::f64::consts::SQRT_2;
+        for j in 0..m {
+            for l in 0..j {
+                let series: f64 = (0..p)
+                    .map(|r| {
+                        (zeta(j, r) * (sqrt2 * xi[l] + eta(l, r))
+                            - zeta(l, r) * (sqrt2 * xi[j] + eta(j, r)))
+                            / (r + 1) as f64
+                    })
+                    .sum();
+                let area =
+                    dt * rho.sqrt() * (mu(j) * xi[l] - mu(l) * xi[j]) + dt / (2.0 * PI) * series;
+                integrals[j * m + l] += area;
+                integrals[l * m + j] -= area;
+            }
+        }
+        integrals
+    }
+}
+
+/// Terms needed for the truncation error of the areas to stay below the
+/// strong order 1.0 error on the finest step, `p >= 1 / (2 pi^2 dt)`.
+fn default_terms(timesteps: &[OrderedFloat<f64>]) -> usize {
+    let dt_min = timesteps
+        .windows(2)
+        .map(|w| (w[1] - w[0]).into_inner())
+        .fold(f64::INFINITY, f64::min);
+    ((1.0 / (2.0 * PI * PI * dt_min)).ceil() as usize).max(1)
+}
+
+/// Diffusion coefficients at the cached state, `[process][driver]`.
+fn diffusion_matrix(
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    drivers: &[usize],
+    t: OrderedFloat<f64>,
+) -> Vec<Vec<f64>> {
+    process_universe
+        .processes
+        .iter()
+        .map(|process| {
+            let mut row = vec![0.0; drivers.len()];
+            for (coefficient, idx) in process.diffusion_terms() {
+                let j = drivers.binary_search(&idx).unwrap();
+                row[j] += coefficient.eval(t, filtration).unwrap();
+            }
+            row
+        })
+        .collect()
+}
+
+/// `L^j f = sum_k b_kj df/dx_k` at the cached state, by a central difference
+/// along the diffusion direction `b_j`. Only the Lévy processes are perturbed,
+/// algebraic processes keep their cached values.
+fn directional_derivative(
+    f: &Function,
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    state: &[f64],
+    direction: &[f64],
+    t: OrderedFloat<f64>,
+) -> f64 {
+    let norm = direction.iter().fold(0.0_f64, |a, b| a.max(b.abs()));
+    if norm == 0.0 {
+        return 0.0;
+    }
+    let scale = state.iter().fold(1.0_f64, |a, b| a.max(b.abs()));
+    let h = f64::EPSILON.cbrt() * scale / norm;
+    let mut eval_at = |sign: f64| {
+        for &p_idx in &process_universe.levy_process_indices {
+            let name = process_universe.processes[p_idx].name();
+            filtration.set_context_value(name, state[p_idx] + sign * h * direction[p_idx]);
+        }
+        f.eval(t, filtration).unwrap()
+    };
+    let derivative = (eval_at(1.0) - eval_at(-1.0)) / (2.0 * h);
+    for &p_idx in &process_universe.levy_process_indices {
+        let name = process_universe.processes[p_idx].name();
+        filtration.set_context_value(name, state[p_idx]);
+    }
+    derivative
+}
+
+/// `L^j b_{i,l}` for every process `i`, diffusion term `(b_{i,l}, l)` and
+/// driver `j`, as `[process][(term, driver)]`.
+pub(crate) fn milstein_derivatives(
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    drivers: &[usize],
+    t: OrderedFloat<f64>,
+) -> Vec<Vec<f64>> {
+    let b = diffusion_matrix(filtration, process_universe, drivers, t);
+    let state: Vec<f64> = process_universe
+        .processes
+        .iter()
+        .map(|p| filtration.cache.values[p.name()])
+        .collect();
+    let mut direction = vec![0.0; b.len()];
+    process_universe
+        .processes
+        .iter()
+        .map(|process| {
+            let mut row = Vec::new();
+            for (coefficient, _) in process.diffusion_terms() {
+                for j in 0..drivers.len() {
+                    for (d, b_k) in direction.iter_mut().zip(b.iter()) {
+                        *d = b_k[j];
+                    }
+                    row.push(directional_derivative(
+                        coefficient,
+                        filtration,
+                        process_universe,
+                        &state,
+                        &direction,
+                        t,
+                    ));
+                }
+            }
+            row
+        })
+        .collect()
+}
+
+/// Checks `L^j b_{i,l} = L^l b_{i,j}` at the initial state.
+fn is_commutative(config: &SimulationConfig, drivers: &[usize]) -> Result<bool, String> {
+    let universe = &config.process_universe;
+    let mut filtration = ScenarioFiltration::new(
+        0,
+        universe.clone(),
+        config.timesteps.clone(),
+        config.initial_values.clone(),
+    );
+    let t = config.timesteps[0];
+    let derivatives = milstein_derivatives(&mut filtration, universe, drivers, t);
+    let m = drivers.len();
+    for (process, row) in universe.processes.iter().zip(derivatives.iter()) {
+        // sum the terms attached to the same driver: L^j b_l as [l][j]
+        let mut lb = vec![0.0; m * m];
+        for (term, (_, idx)) in process.diffusion_terms().into_iter().enumerate() {
+            let l = drivers.binary_search(&idx).unwrap();
+            for j in 0..m {
+                lb[l * m + j] += row[term * m + j];
+            }
+        }
+        for j in 0..m {
+            for l in 0..j {
+                let (a, b) = (lb[l * m + j], lb[j * m + l]);
+                if !(a.is_finite() && b.is_finite()) {
+                    return Err(format!(
+                        "Cannot check commutativity of the noise of '{}' at the initial state",
+                        process.name()
+                    ));
+                }
+                if (a - b).abs() > COMMUTATIVITY_TOL * a.abs().max(b.abs()).max(1.0) {
+                    return Ok(false);
+                }
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Milstein step: Euler plus the second order terms
+/// `sum_{j,l} L^j b_l I_(j,l)` of every Wiener driver. Jump and other terms are
+/// treated as in Euler.
+pub fn milstein_iteration(
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    t_idx: usize,
+    rng: &mut dyn BaseRng,
+    levy_areas: &LevyAreas,
+) {
+    let current_time = filtration.times[t_idx];
+    let next_time = filtration.times[t_idx + 1];
+    let dt = (next_time - current_time).into_inner();
+    filtration.refresh_cache(current_time);
+
+    // Euler part, keeping the step's Brownian increments per driver
+    let drivers = &levy_areas.drivers;
+    let mut dw = vec![0.0; drivers.len()];
+    let mut next = vec![0.0; process_universe.processes.len()];
+    for p_idx in &process_universe.levy_process_indices {
+        if let Process::Levy(levy) = &process_universe.processes[*p_idx] {
+            let mut val = filtration.get(t_idx, *p_idx);
+            for (coefficient, incrementor) in levy.coefficients.iter().zip(&levy.incrementors) {
+                let c = coefficient.eval(current_time, filtration).unwrap();
+                let mut x = incrementor.sample(t_idx, filtration, rng);
+                if let Some(idx) = incrementor.stochastic_idx() {
+                    x = filtration.shift_increment(idx, x);
+                    filtration.record_increment(t_idx, idx, x);
+                    if incrementor.is_wiener() {
+                        dw[drivers.binary_search(&idx).unwrap()] = x;
+                    }
+                }
+                val += c * x;
+            }
+            next[*p_idx] = val;
+        }
+    }
+
+    // Milstein correction
+    let m = drivers.len();
+    if m > 0 {
+        let integrals = levy_areas.iterated_integrals(t_idx, rng, &dw, dt);
+        let derivatives = milstein_derivatives(filtration, process_universe, drivers, current_time);
+        for p_idx in &process_universe.levy_process_indices {
+            let process = &process_universe.processes[*p_idx];
+            let row = &derivatives[*p_idx];
+            for (term, (_, idx)) in process.diffusion_terms().into_iter().enumerate() {
+                let l = drivers.binary_search(&idx).unwrap();
+                for j in 0..m {
+                    next[*p_idx] += row[term * m + j] * integrals[j * m + l];
+                }
+            }
+        }
+    }
+
+    for p_idx in &process_universe.levy_process_indices {
+        filtration.set(t_idx + 1, *p_idx, next[*p_idx]);
+    }
+    for p_idx in &process_universe.algebraic_process_indices {
+        if let Process::Algebraic(alg) = &process_universe.processes[*p_idx] {
+            let val = alg.coefficients[0].eval(next_time, filtration).unwrap();
+            filtration.set(t_idx + 1, *p_idx, val);
+        }
+    }
+}
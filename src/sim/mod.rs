@@ -1,22 +1,48 @@
+mod adaptive;
+pub mod config;
+pub mod engine;
 pub mod euler;
+pub mod exp_euler;
+mod importance;
+mod matching;
+pub mod milstein;
+pub mod reduce;
+#[cfg(feature = "dataframe")]
+pub mod risk;
+pub mod rqmc;
 pub mod runge_kutta;
 
-use crate::filtration::ScenarioFiltration;
-use crate::proc::ProcessUniverse;
+pub use engine::SimulationEngine;
+pub use rqmc::rqmc_error;
+
+use crate::filtration::{Filtration, ScenarioFiltration};
+use crate::func::Aggregate;
+use crate::proc::{Process, ProcessUniverse, ValueKind};
+use crate::rng::copula::{Copula, CopulaRng};
+use crate::rng::rqmc::{RqmcPointSet, RqmcRng, replication_of};
 use crate::rng::sobol::SobolEngine;
-use crate::rng::{BaseRng, pseudo::PseudoRng, sobol::SobolRng};
+use crate::rng::{BaseRng, philox::PhiloxRng, pseudo::PseudoRng, sobol::SobolRng};
+use config::{SimulationConfig, SimulationOrder};
+use importance::DriftShifts;
+use matching::MomentMatcher;
+use milstein::LevyAreas;
 use ordered_float::OrderedFloat;
+#[cfg(feature = "os-rng")]
 use rand::Rng;
 use rayon::prelude::*;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+const SCHEMES: [&str; 4] = ["euler", "exp-euler", "milstein", "runge-kutta"];
+const RNG_METHODS: [&str; 3] = ["pseudo", "philox", "sobol"];
+
 /// Run a batch of simulation paths in parallel and return a concatenated DataFrame.
 ///
 /// Each scenario is executed independently on its own `ScenarioFiltration`.  The
 /// numerical scheme and RNG algorithm are chosen by the `scheme`/`rng_method`
 /// parameters.  When `rng_method` is "sobol" a shared Sobol engine is used to
 /// avoid rebuilding the sequence for every path.
+#[cfg(feature = "dataframe")]
 pub fn simulate(
     process_universe: &ProcessUniverse,
     timesteps: Vec<OrderedFloat<f64>>,
@@ -25,68 +51,709 @@ pub fn simulate(
     scheme: &str,
     rng_method: &str,
 ) -> polars::prelude::PolarsResult<polars::prelude::LazyFrame> {
-    let mut rng = rand::rng();
-    let random_seed: u64 = rng.random();
-    let times = timesteps;
-    let num_time_deltas = times.len() - 1;
-    let sobol_increments = process_universe.stochastic_registry.len();
-    let sobol_dims = num_time_deltas * sobol_increments;
-
-    // shared Sobol engine (only used when rng_method == "sobol")
-    let shared_engine = match rng_method {
-        "sobol" => Some(Arc::new(Mutex::new(SobolEngine::new(sobol_dims)))),
-        _ => None,
+    let filtration = simulate_filtration(
+        process_universe,
+        timesteps,
+        initial_values,
+        num_scenarios,
+        scheme,
+        rng_method,
+    )
+    .map_err(|e| polars::prelude::PolarsError::ComputeError(e.into()))?;
+    Ok(filtration.to_lazyframe())
+}
+
+/// Same as [`simulate`], but returns the in-memory [`Filtration`] holding every
+/// scenario so that cross-sectional post-processing can run without Polars.
+pub fn simulate_filtration(
+    process_universe: &ProcessUniverse,
+    timesteps: Vec<OrderedFloat<f64>>,
+    initial_values: HashMap<String, f64>,
+    num_scenarios: u64,
+    scheme: &str,
+    rng_method: &str,
+) -> Result<Filtration, String> {
+    let config = SimulationConfig::new(
+        process_universe.clone(),
+        timesteps,
+        initial_values,
+        num_scenarios,
+    )
+    .with_scheme(scheme)
+    .with_rng_method(rng_method);
+    simulate_with_config(&config)
+}
+
+/// Runs the simulation described by `config`.
+pub fn simulate_with_config(config: &SimulationConfig) -> Result<Filtration, String> {
+    let aggregates = validate(config)?;
+    let seed = master_seed(config)?;
+    let stepper = Stepper::new(config)?;
+    let rng_factory = RngFactory::new(config, seed, stepper.auxiliary_dims())?;
+    if let Some(settings) = &config.adaptive {
+        return simulate_adaptive(config, settings, &stepper, &rng_factory, seed);
+    }
+
+    let scenario_filtrations = match config.order {
+        SimulationOrder::ScenarioMajor => run_scenario_major(config, &stepper, &rng_factory),
+        SimulationOrder::TimeMajor => {
+            let mut states = new_states(config, &rng_factory);
+            let starts = vec![0; states.len()];
+            run_states(config, &stepper, &mut states, &starts, &aggregates);
+            states.into_iter().map(|(f, _, _)| f).collect()
+        }
     };
+    let mut filtration = Filtration::from_scenarios(scenario_filtrations);
+    filtration.value_kinds = stepper.value_kinds.clone();
+    assign_replications(config, &mut filtration);
+    Ok(filtration)
+}
 
-    let dfs: Vec<polars::prelude::LazyFrame> = (0..num_scenarios)
+fn simulate_adaptive(
+    config: &SimulationConfig,
+    settings: &config::AdaptiveStepping,
+    stepper: &Stepper,
+    rng_factory: &RngFactory,
+    seed: u64,
+) -> Result<Filtration, String> {
+    let paths: Vec<(ScenarioFiltration, usize)> = scenario_range(config)
         .into_par_iter()
         .map(|s_idx| {
-            // build a fresh filtration for this scenario
-            let local_process_universe = process_universe.clone();
-            let mut filtration = ScenarioFiltration::new(
-                s_idx as i32,
-                local_process_universe.clone(),
-                times.clone(),
-                initial_values.clone(),
-            );
+            let (mut filtration, local_process_universe) = new_scenario(config, s_idx);
+            let mut local_rng = rng_factory.build(s_idx);
+            let steps =
+                adaptive::AdaptivePath::new(settings, stepper, &local_process_universe, seed)
+                    .simulate(&mut filtration, local_rng.as_mut())?;
+            Ok((filtration, steps))
+        })
+        .collect::<Result<_, String>>()?;
+    let (scenario_filtrations, steps): (Vec<_>, Vec<_>) = paths.into_iter().unzip();
+    let mut filtration = Filtration::from_scenarios(scenario_filtrations);
+    filtration.value_kinds = stepper.value_kinds.clone();
+    filtration.step_counts = Some(steps);
+    assign_replications(config, &mut filtration);
+    Ok(filtration)
+}
 
-            // every scenario gets its own RNG instance
-            let mut local_rng: Box<dyn BaseRng> = match rng_method {
-                "sobol" => Box::new(SobolRng::new(
-                    s_idx + random_seed,
-                    Arc::clone(
-                        shared_engine
-                            .as_ref()
-                            .expect("Sobol engine not initialized"),
-                    ),
-                    sobol_increments,
-                    times.len(),
+/// Continues a filtration that was extended with [`Filtration::extend_times`]
+/// or [`Filtration::append_scenarios`], simulating only the cells not yet
+/// populated. Each scenario resumes from its last simulated time with its full
+/// history (so delayed terms see it) and draws from its own substream, so with
+/// the same seed and the `"pseudo"` or `"philox"` generators the result equals
+/// an uninterrupted run, and already simulated cells are left untouched.
+///
+/// `config` must describe the filtration's processes on its full time grid;
+/// its initial values and scenario count are ignored.
+pub fn resume_with_config(
+    config: &SimulationConfig,
+    filtration: &mut Filtration,
+) -> Result<(), String> {
+    let aggregates = validate(config)?;
+    if config.adaptive.is_some() {
+        return Err("Adaptive stepping does not support resuming".into());
+    }
+    if config.moment_matching.is_some() {
+        return Err("Moment matching does not support resuming".into());
+    }
+    if config.timesteps != filtration.times {
+        return Err("The config's time grid differs from the filtration's".into());
+    }
+    let process_names: Vec<&str> = config
+        .process_universe
+        .processes
+        .iter()
+        .map(|p| p.name())
+        .collect();
+    if process_names != filtration.process_names {
+        return Err("The config's processes differ from the filtration's".into());
+    }
+    if config.record_increments && !filtration.has_increments() {
+        return Err("Cannot record increments when resuming a filtration without them".into());
+    }
+    if let Some(replications) = config.replications
+        && filtration
+            .scenarios
+            .iter()
+            .any(|s| *s as u64 >= config.first_scenario + config.num_scenarios)
+    {
+        return Err(format!(
+            "Scenarios beyond the {} covered by the {} replications cannot be resumed",
+            config.first_scenario + config.num_scenarios,
+            replications
+        ));
+    }
+    if !config.drift_shifts.is_empty() && filtration.weights.is_none() {
+        // the simulated part was drawn under the original measure
+        filtration.weights = Some(vec![1.0; filtration.num_scenarios()]);
+    }
+
+    let seed = master_seed(config)?;
+    let stepper = Stepper::new(config)?;
+    let rng_factory = RngFactory::new(config, seed, stepper.auxiliary_dims())?;
+    let resumed: Vec<usize> = (0..filtration.num_scenarios())
+        .filter(|s_idx| filtration.simulated_len(*s_idx) < filtration.times.len())
+        .collect();
+    let shared: &Filtration = filtration;
+    let mut states: Vec<ScenarioState> = resumed
+        .par_iter()
+        .map(|s_idx| {
+            let id = shared.scenarios[*s_idx];
+            let (mut f, local_process_universe) = new_scenario(config, id as u64);
+            shared.load_scenario(*s_idx, &mut f);
+            (f, local_process_universe, rng_factory.build(id as u64))
+        })
+        .collect();
+    let starts: Vec<usize> = resumed
+        .iter()
+        .map(|s_idx| filtration.simulated_len(*s_idx) - 1)
+        .collect();
+    run_states(config, &stepper, &mut states, &starts, &aggregates);
+    for (s_idx, (f, _, _)) in resumed.iter().zip(states.iter()) {
+        filtration.store_scenario(*s_idx, f);
+    }
+    filtration.value_kinds = stepper.value_kinds.clone();
+    assign_replications(config, filtration);
+    Ok(())
+}
+
+/// The configured seed, or a random one drawn from OS entropy.
+#[cfg(feature = "os-rng")]
+fn master_seed(config: &SimulationConfig) -> Result<u64, String> {
+    Ok(config.seed.unwrap_or_else(|| rand::rng().random()))
+}
+
+/// The configured seed; without the `os-rng` feature there is no entropy
+/// source to draw one from.
+#[cfg(not(feature = "os-rng"))]
+fn master_seed(config: &SimulationConfig) -> Result<u64, String> {
+    config.seed.ok_or_else(|| {
+        "No seed given; set one with `with_seed` or enable the os-rng feature".into()
+    })
+}
+
+/// Checks `config` before simulating and returns the mean-field terms it uses.
+fn validate(config: &SimulationConfig) -> Result<Vec<Aggregate>, String> {
+    if !SCHEMES.contains(&config.scheme.as_str()) {
+        return Err(format!(
+            "Unknown scheme '{}' (expected one of: {})",
+            config.scheme,
+            SCHEMES.join(", ")
+        ));
+    }
+    if !RNG_METHODS.contains(&config.rng_method.as_str()) {
+        return Err(format!(
+            "Unknown rng method '{}' (expected one of: {})",
+            config.rng_method,
+            RNG_METHODS.join(", ")
+        ));
+    }
+    if config.timesteps.len() < 2 {
+        return Err("At least two time steps are required".into());
+    }
+
+    let aggregates = config.process_universe.aggregates();
+    for agg in &aggregates {
+        if !config
+            .process_universe
+            .process_registry
+            .contains_key(&agg.process)
+        {
+            return Err(format!(
+                "Mean-field term '{}' references unknown process '{}'",
+                agg.var_name, agg.process
+            ));
+        }
+    }
+    for lag in config.process_universe.lags() {
+        if !config
+            .process_universe
+            .process_registry
+            .contains_key(&lag.process)
+        {
+            return Err(format!(
+                "Delayed term 'lag({}, {})' references unknown process '{}'",
+                lag.process, lag.lag, lag.process
+            ));
+        }
+    }
+    if !aggregates.is_empty() && config.order != SimulationOrder::TimeMajor {
+        return Err(format!(
+            "Mean-field terms ({}) require time-major simulation order",
+            aggregates
+                .iter()
+                .map(|a| a.var_name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+    }
+
+    if let Some(settings) = &config.adaptive {
+        adaptive::validate(config, settings)?;
+    }
+    if let Some(replications) = config.replications
+        && (replications == 0 || replications as u64 > config.num_scenarios)
+    {
+        return Err(format!(
+            "replications must lie between 1 and the number of scenarios ({}), got {}",
+            config.num_scenarios, replications
+        ));
+    }
+    Ok(aggregates)
+}
+
+/// Value kind of every process: the configured one, else `Integer` when the
+/// equation implies it and the initial value is a whole number.
+fn value_kinds(config: &SimulationConfig) -> Result<Vec<ValueKind>, String> {
+    let universe = &config.process_universe;
+    if let Some(name) = config
+        .value_kinds
+        .keys()
+        .find(|name| !universe.process_registry.contains_key(*name))
+    {
+        return Err(format!("Value kind given for unknown process '{}'", name));
+    }
+    universe
+        .processes
+        .iter()
+        .map(|p| {
+            let initial = config.initial_values.get(p.name()).copied().unwrap_or(0.0);
+            let integral = initial.fract() == 0.0;
+            match config.value_kinds.get(p.name()) {
+                Some(ValueKind::Integer) if !integral => Err(format!(
+                    "Integer process '{}' needs an integer initial value, got {}",
+                    p.name(),
+                    initial
                 )),
-                _ => Box::new(PseudoRng::new(s_idx + random_seed, sobol_increments)),
-            };
-
-            for t_idx in 0..num_time_deltas {
-                match scheme {
-                    "euler" => euler::euler_iteration(
-                        &mut filtration,
-                        &local_process_universe,
-                        t_idx,
-                        local_rng.as_mut(),
-                    ),
-                    "runge-kutta" => runge_kutta::runge_kutta_iteration(
-                        &mut filtration,
-                        &local_process_universe,
-                        t_idx,
-                        local_rng.as_mut(),
-                    ),
-                    _ => unimplemented!(),
-                }
+                Some(kind) => Ok(*kind),
+                None if integral => Ok(p.value_kind()),
+                None => Ok(ValueKind::Continuous),
+            }
+        })
+        .collect()
+}
+
+fn assign_replications(config: &SimulationConfig, filtration: &mut Filtration) {
+    filtration.replications = config.replications.map(|replications| {
+        filtration
+            .scenarios
+            .iter()
+            .map(|s| replication_of(*s as u64, replications).0)
+            .collect()
+    });
+}
+
+/// Builds the random source of every scenario of a run.
+struct RngFactory {
+    seed: u64,
+    rng_method: String,
+    num_timesteps: usize,
+    first_scenario: u64,
+    replications: Option<usize>,
+    /// Uniforms per step seen by the scheme (drivers plus scheme auxiliaries).
+    num_increments: usize,
+    /// Uniforms per step drawn from the underlying source.
+    source_increments: usize,
+    /// shared Sobol engine (only used when rng_method == "sobol")
+    shared_engine: Option<Arc<Mutex<SobolEngine>>>,
+    /// common point set of a randomized QMC run, one point per scenario of a replication
+    rqmc_points: Option<Arc<RqmcPointSet>>,
+    copula: Option<(Vec<usize>, Arc<dyn Copula>)>,
+}
+
+impl RngFactory {
+    /// `auxiliary_dims` are the uniforms the scheme draws per step besides the
+    /// drivers.
+    fn new(config: &SimulationConfig, seed: u64, auxiliary_dims: usize) -> Result<Self, String> {
+        let copula = match &config.copula {
+            Some((names, copula)) => Some((
+                copula_indices(&config.process_universe, names, copula.as_ref())?,
+                Arc::clone(copula),
+            )),
+            None => None,
+        };
+        let num_increments = config.process_universe.stochastic_registry.len() + auxiliary_dims;
+        // a copula may consume further latent uniforms, appended after those
+        let source_increments =
+            num_increments + copula.as_ref().map_or(0, |(_, c)| c.latent_dims());
+        let sobol_dims = (config.timesteps.len() - 1) * source_increments;
+        let rqmc_points = match (config.rng_method.as_str(), config.replications) {
+            ("sobol", Some(replications)) => Some(Arc::new(RqmcPointSet::new(
+                sobol_dims,
+                (config.first_scenario + config.num_scenarios).div_ceil(replications as u64)
+                    as usize,
+            ))),
+            _ => None,
+        };
+        let mut factory = Self {
+            seed,
+            rng_method: config.rng_method.clone(),
+            num_timesteps: config.timesteps.len(),
+            first_scenario: config.first_scenario,
+            replications: config.replications,
+            num_increments,
+            source_increments,
+            shared_engine: None,
+            rqmc_points,
+            copula,
+        };
+        factory.reseed(seed);
+        Ok(factory)
+    }
+
+    /// Starts over with a new seed, restarting the shared Sobol sequence.
+    fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        if self.rng_method == "sobol" && self.replications.is_none() {
+            let sobol_dims = (self.num_timesteps - 1) * self.source_increments;
+            self.shared_engine = Some(Arc::new(Mutex::new(SobolEngine::starting_at(
+                sobol_dims,
+                self.first_scenario,
+            ))));
+        }
+    }
+
+    /// Random source of scenario `s_idx`, determined by the seed and `s_idx`
+    /// alone.
+    fn build(&self, s_idx: u64) -> Box<dyn BaseRng> {
+        let source = self.build_source(s_idx);
+        match &self.copula {
+            Some((indices, copula)) => Box::new(CopulaRng::new(
+                source,
+                Arc::clone(copula),
+                indices.clone(),
+                self.num_increments,
+            )),
+            None => source,
+        }
+    }
+
+    fn build_source(&self, s_idx: u64) -> Box<dyn BaseRng> {
+        if let (Some(points), Some(replications)) = (self.rqmc_points.as_ref(), self.replications) {
+            let (replication, point_idx) = replication_of(s_idx, replications);
+            return Box::new(RqmcRng::new(
+                points,
+                point_idx,
+                self.seed.wrapping_add(replication as u64),
+                self.source_increments,
+                self.num_timesteps,
+            ));
+        }
+        match self.rng_method.as_str() {
+            "sobol" => Box::new(SobolRng::for_scenario(
+                self.seed,
+                self.shared_engine
+                    .as_ref()
+                    .expect("Sobol engine not initialized"),
+                s_idx,
+                self.source_increments,
+                self.num_timesteps,
+            )),
+            "philox" => Box::new(PhiloxRng::new(self.seed, s_idx)),
+            _ => Box::new(PseudoRng::for_scenario(
+                self.seed,
+                s_idx,
+                self.source_increments,
+            )),
+        }
+    }
+}
+
+/// Resolves an increment name to its stochastic index. A name matches a
+/// registered increment exactly or up to its arguments, so `"dN1"` refers to
+/// `dN1(0.5)`.
+fn resolve_increment(process_universe: &ProcessUniverse, name: &str) -> Result<usize, String> {
+    let matches: Vec<usize> = process_universe
+        .stochastic_registry
+        .iter()
+        .filter(|(key, _)| *key == name || key.split('(').next() == Some(name))
+        .map(|(_, idx)| *idx)
+        .collect();
+    match matches.as_slice() {
+        [idx] => Ok(*idx),
+        [] => Err(format!("Unknown increment '{}'", name)),
+        _ => Err(format!("Increment name '{}' is ambiguous", name)),
+    }
+}
+
+/// Resolves the increments coupled by a copula to their stochastic indices.
+fn copula_indices(
+    process_universe: &ProcessUniverse,
+    names: &[String],
+    copula: &dyn Copula,
+) -> Result<Vec<usize>, String> {
+    if let Some(dim) = copula.dimension()
+        && dim != names.len()
+    {
+        return Err(format!(
+            "Copula has dimension {} but couples {} increments",
+            dim,
+            names.len()
+        ));
+    }
+    let mut indices: Vec<usize> = Vec::with_capacity(names.len());
+    for name in names {
+        let idx =
+            resolve_increment(process_universe, name).map_err(|e| format!("Copula: {}", e))?;
+        if indices.contains(&idx) {
+            return Err(format!("Copula couples increment '{}' twice", name));
+        }
+        indices.push(idx);
+    }
+    Ok(indices)
+}
+
+fn new_scenario(config: &SimulationConfig, s_idx: u64) -> (ScenarioFiltration, ProcessUniverse) {
+    // every scenario works on its own copy of the processes
+    let local_process_universe = config.process_universe.clone();
+    let mut filtration = ScenarioFiltration::new(
+        s_idx as i32,
+        local_process_universe.clone(),
+        config.timesteps.clone(),
+        config.initial_values.clone(),
+    );
+    if config.record_increments {
+        filtration.enable_increment_recording();
+    }
+    if !config.drift_shifts.is_empty() {
+        filtration.enable_importance_sampling();
+    }
+    (filtration, local_process_universe)
+}
+
+/// Advances a scenario by one step with the configured scheme.
+struct Stepper {
+    scheme: String,
+    drift_shifts: Option<DriftShifts>,
+    levy_areas: Option<LevyAreas>,
+    value_kinds: Vec<ValueKind>,
+    /// Indices of the integer processes, rounded after every step.
+    integer_processes: Vec<usize>,
+    /// Linear drift coefficients solved exactly by `"exp-euler"`.
+    linear_drifts: Vec<Option<f64>>,
+    moment_matcher: Option<MomentMatcher>,
+}
+
+impl Stepper {
+    fn new(config: &SimulationConfig) -> Result<Self, String> {
+        let levy_areas = match config.scheme.as_str() {
+            "milstein" => Some(LevyAreas::new(config)?),
+            _ => None,
+        };
+        let value_kinds = value_kinds(config)?;
+        let integer_processes = value_kinds
+            .iter()
+            .enumerate()
+            .filter(|(_, kind)| **kind == ValueKind::Integer)
+            .map(|(p_idx, _)| p_idx)
+            .collect();
+        Ok(Self {
+            scheme: config.scheme.clone(),
+            drift_shifts: DriftShifts::new(config)?,
+            levy_areas,
+            value_kinds,
+            integer_processes,
+            linear_drifts: exp_euler::linear_drifts(config)?,
+            moment_matcher: MomentMatcher::new(config)?,
+        })
+    }
+
+    /// Uniforms drawn per step on top of the stochastic drivers: the sign of
+    /// the Runge-Kutta perturbation, or the normals of the Lévy areas.
+    fn auxiliary_dims(&self) -> usize {
+        match (self.scheme.as_str(), &self.levy_areas) {
+            ("runge-kutta", _) => 1,
+            (_, Some(levy_areas)) => levy_areas.dims(),
+            _ => 0,
+        }
+    }
+
+    fn step(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        process_universe: &ProcessUniverse,
+        t_idx: usize,
+        rng: &mut dyn BaseRng,
+    ) {
+        if let Some(drift_shifts) = &self.drift_shifts {
+            drift_shifts.apply(filtration, t_idx, rng);
+        }
+        match self.scheme.as_str() {
+            "euler" => euler::euler_iteration(filtration, process_universe, t_idx, rng),
+            "exp-euler" => exp_euler::exp_euler_iteration(
+                filtration,
+                process_universe,
+                t_idx,
+                rng,
+                &self.linear_drifts,
+            ),
+            "milstein" => milstein::milstein_iteration(
+                filtration,
+                process_universe,
+                t_idx,
+                rng,
+                self.levy_areas.as_ref().unwrap(),
+            ),
+            "runge-kutta" => {
+                runge_kutta::runge_kutta_iteration(filtration, process_universe, t_idx, rng)
+            }
+            _ => unreachable!("scheme '{}' was validated before simulating", self.scheme),
+        }
+        if !self.integer_processes.is_empty() {
+            self.round_integers(filtration, process_universe, t_idx);
+        }
+    }
+
+    /// Rounds the integer processes at `t_idx + 1` to the nearest whole number
+    /// and, if any moved, settles the algebraic processes again so they see
+    /// the rounded values.
+    fn round_integers(
+        &self,
+        filtration: &mut ScenarioFiltration,
+        process_universe: &ProcessUniverse,
+        t_idx: usize,
+    ) {
+        let mut moved = false;
+        for p_idx in &self.integer_processes {
+            let val = filtration.get(t_idx + 1, *p_idx);
+            if val.round() != val {
+                filtration.set(t_idx + 1, *p_idx, val.round());
+                moved = true;
             }
+        }
+        if !moved {
+            return;
+        }
+        let next_time = filtration.times[t_idx + 1];
+        filtration.refresh_cache(next_time);
+        for p_idx in &process_universe.algebraic_process_indices {
+            if let Process::Algebraic(alg) = &process_universe.processes[*p_idx] {
+                let val = alg.coefficients[0].eval(next_time, filtration).unwrap();
+                filtration.set(t_idx + 1, *p_idx, val);
+            }
+        }
+    }
+}
 
-            filtration.to_lazyframe()
+/// A scenario's path, its private copy of the processes and its random source.
+type ScenarioState = (ScenarioFiltration, ProcessUniverse, Box<dyn BaseRng>);
+
+/// Numbers of the scenarios a run simulates.
+fn scenario_range(config: &SimulationConfig) -> std::ops::Range<u64> {
+    config.first_scenario..config.first_scenario + config.num_scenarios
+}
+
+fn new_states(config: &SimulationConfig, rng_factory: &RngFactory) -> Vec<ScenarioState> {
+    scenario_range(config)
+        .into_par_iter()
+        .map(|s_idx| {
+            let (filtration, local_process_universe) = new_scenario(config, s_idx);
+            (filtration, local_process_universe, rng_factory.build(s_idx))
+        })
+        .collect()
+}
+
+/// Simulates a path from step `first_step` to the end of the grid.
+fn simulate_path(
+    stepper: &Stepper,
+    filtration: &mut ScenarioFiltration,
+    process_universe: &ProcessUniverse,
+    rng: &mut dyn BaseRng,
+    first_step: usize,
+) {
+    for t_idx in first_step..filtration.times.len() - 1 {
+        stepper.step(filtration, process_universe, t_idx, rng);
+    }
+}
+
+fn run_scenario_major(
+    config: &SimulationConfig,
+    stepper: &Stepper,
+    rng_factory: &RngFactory,
+) -> Vec<ScenarioFiltration> {
+    scenario_range(config)
+        .into_par_iter()
+        .map(|s_idx| {
+            let (mut filtration, local_process_universe) = new_scenario(config, s_idx);
+            // every scenario gets its own RNG instance
+            let mut local_rng = rng_factory.build(s_idx);
+            simulate_path(
+                stepper,
+                &mut filtration,
+                &local_process_universe,
+                local_rng.as_mut(),
+                0,
+            );
+            filtration
         })
+        .collect()
+}
+
+/// Simulates already prepared scenario states over the time grid, each from
+/// its step in `starts`.
+fn run_states(
+    config: &SimulationConfig,
+    stepper: &Stepper,
+    states: &mut [ScenarioState],
+    starts: &[usize],
+    aggregates: &[Aggregate],
+) {
+    match config.order {
+        SimulationOrder::ScenarioMajor => {
+            states.par_iter_mut().zip(starts).for_each(
+                |((filtration, local_process_universe, local_rng), &first_step)| {
+                    simulate_path(
+                        stepper,
+                        filtration,
+                        local_process_universe,
+                        local_rng.as_mut(),
+                        first_step,
+                    )
+                },
+            );
+        }
+        SimulationOrder::TimeMajor => run_time_major(config, stepper, states, starts, aggregates),
+    }
+}
+
+fn run_time_major(
+    config: &SimulationConfig,
+    stepper: &Stepper,
+    states: &mut [ScenarioState],
+    starts: &[usize],
+    aggregates: &[Aggregate],
+) {
+    let num_time_deltas = config.timesteps.len() - 1;
+    let first_step = starts.iter().copied().min().unwrap_or(num_time_deltas);
+    let aggregate_processes: Vec<usize> = aggregates
+        .iter()
+        .map(|a| config.process_universe.process_registry[&a.process])
         .collect();
 
-    // stack all of the individual scenario frames together
-    polars::prelude::concat(&dfs, polars::prelude::UnionArgs::default())
+    let mut cross_section = Vec::with_capacity(states.len());
+    for t_idx in first_step..num_time_deltas {
+        // Mean-field terms are computed once per step from the step-start values
+        for (agg, &p_idx) in aggregates.iter().zip(aggregate_processes.iter()) {
+            cross_section.clear();
+            cross_section.extend(states.iter().map(|(f, _, _)| f.get(t_idx, p_idx)));
+            let val = agg.compute(&mut cross_section);
+            for (filtration, _, _) in states.iter_mut() {
+                filtration.set_context_value(&agg.var_name, val);
+            }
+        }
+        if let Some(matcher) = &stepper.moment_matcher {
+            matcher.apply(states, starts, t_idx);
+        }
+        states
+            .par_iter_mut()
+            .zip(starts)
+            .filter(|(_, start)| **start <= t_idx)
+            .for_each(|((filtration, local_process_universe, local_rng), _)| {
+                stepper.step(
+                    filtration,
+                    local_process_universe,
+                    t_idx,
+                    local_rng.as_mut(),
+                )
+            });
+    }
 }
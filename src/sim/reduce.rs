@@ -0,0 +1,243 @@
+use crate::filtration::Filtration;
+use ordered_float::OrderedFloat;
+#[cfg(feature = "dataframe")]
+use polars::prelude::*;
+use rayon::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Result of a scenario reduction.
+pub struct Reduction {
+    /// The kept scenarios. Its `weights` are `probabilities` scaled by the
+    /// number of kept scenarios, so weighted means are plain averages of
+    /// `weight * value`, as for importance sampling.
+    pub filtration: Filtration,
+    /// Probability of every kept scenario, summing to 1.
+    pub probabilities: Vec<f64>,
+    /// Position in `filtration` of the kept scenario each original scenario
+    /// (by index in the input) is mapped to.
+    pub assignment: Vec<usize>,
+    /// Ids of the original scenarios.
+    #[cfg(feature = "dataframe")]
+    original_scenarios: Vec<i32>,
+}
+
+impl Reduction {
+    fn new(
+        full: &Filtration,
+        kept: Vec<usize>,
+        probabilities: Vec<f64>,
+        assignment: Vec<usize>,
+    ) -> Result<Self, String> {
+        let mut filtration = full.select_scenarios(&kept)?;
+        let n = kept.len() as f64;
+        filtration.weights = Some(probabilities.iter().map(|p| p * n).collect());
+        Ok(Self {
+            filtration,
+            probabilities,
+            assignment,
+            #[cfg(feature = "dataframe")]
+            original_scenarios: full.scenarios.clone(),
+        })
+    }
+
+    /// Frame with columns `original_scenario` and `kept_scenario` (ids).
+    #[cfg(feature = "dataframe")]
+    pub fn mapping(&self) -> PolarsResult<DataFrame> {
+        let kept: Vec<i32> = self
+            .assignment
+            .iter()
+            .map(|&k| self.filtration.scenarios[k])
+            .collect();
+        DataFrame::new(vec![
+            Column::new("original_scenario".into(), self.original_scenarios.clone()),
+            Column::new("kept_scenario".into(), kept),
+        ])
+    }
+}
+
+/// Probability of every scenario: normalized likelihood-ratio weights under
+/// importance sampling, uniform otherwise.
+fn scenario_probabilities(filtration: &Filtration) -> Vec<f64> {
+    let n = filtration.num_scenarios();
+    match &filtration.weights {
+        Some(weights) => {
+            let total: f64 = weights.iter().sum();
+            weights.iter().map(|w| w / total).collect()
+        }
+        None => vec![1.0 / n as f64; n],
+    }
+}
+
+fn terminal_values(filtration: &Filtration, process: &str) -> Result<Vec<f64>, String> {
+    let time = filtration
+        .times
+        .last()
+        .ok_or("The filtration has no time points")?;
+    filtration.process_values(time.0, process)
+}
+
+/// Keeps one scenario per stratum of equal probability of the terminal value
+/// of `process`: the scenario whose terminal value is nearest the stratum's
+/// conditional mean, carrying the stratum's probability.
+pub fn stratified(
+    filtration: &Filtration,
+    process: &str,
+    strata: usize,
+) -> Result<Reduction, String> {
+    let n = filtration.num_scenarios();
+    if strata == 0 || strata > n {
+        return Err(format!(
+            "The number of strata must lie between 1 and the number of scenarios ({}), got {}",
+            n, strata
+        ));
+    }
+    let values = terminal_values(filtration, process)?;
+    if values.iter().any(|v| !v.is_finite()) {
+        return Err(format!(
+            "Terminal values of '{}' are not all finite",
+            process
+        ));
+    }
+    let probabilities = scenario_probabilities(filtration);
+    let mut order: Vec<usize> = (0..n).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut kept = Vec::with_capacity(strata);
+    let mut kept_probabilities = Vec::with_capacity(strata);
+    let mut assignment = vec![0; n];
+    let mut cumulative = 0.0;
+    let mut start = 0;
+    for k in 0..strata {
+        // scenarios whose probability mass is centered below the stratum's upper edge
+        let upper = (k + 1) as f64 / strata as f64;
+        let mut end = start;
+        while end < n && (k + 1 == strata || cumulative + 0.5 * probabilities[order[end]] < upper) {
+            cumulative += probabilities[order[end]];
+            end += 1;
+        }
+        let members = &order[start..end];
+        start = end;
+        let mass: f64 = members.iter().map(|&i| probabilities[i]).sum();
+        if members.is_empty() || mass <= 0.0 {
+            continue;
+        }
+        let mean = members
+            .iter()
+            .map(|&i| probabilities[i] * values[i])
+            .sum::<f64>()
+            / mass;
+        let representative = *members
+            .iter()
+            .min_by_key(|&&i| OrderedFloat((values[i] - mean).abs()))
+            .unwrap();
+        for &i in members {
+            assignment[i] = kept.len();
+        }
+        kept.push(representative);
+        kept_probabilities.push(mass);
+    }
+    Reduction::new(filtration, kept, kept_probabilities, assignment)
+}
+
+/// Fast forward selection (Heitsch & Römisch): greedily keeps `keep` scenarios
+/// minimizing the probability-weighted Euclidean distance of every scenario to
+/// its nearest kept one, over the `(process, time)` coordinates. The mass of
+/// every dropped scenario moves to its nearest kept scenario.
+///
+/// The first two steps scan all pairs of scenarios, so the cost grows as
+/// `n^2`; later steps are evaluated lazily.
+pub fn forward_selection(
+    filtration: &Filtration,
+    coordinates: &[(&str, f64)],
+    keep: usize,
+) -> Result<Reduction, String> {
+    let n = filtration.num_scenarios();
+    if keep == 0 || keep > n {
+        return Err(format!(
+            "The number of kept scenarios must lie between 1 and the number of scenarios ({}), got {}",
+            n, keep
+        ));
+    }
+    if coordinates.is_empty() {
+        return Err("At least one (process, time) coordinate is required".into());
+    }
+    let columns: Vec<Vec<f64>> = coordinates
+        .iter()
+        .map(|(process, time)| filtration.process_values(*time, process))
+        .collect::<Result<_, _>>()?;
+    // scenario-major points for cache-friendly distance evaluation
+    let dim = columns.len();
+    let points: Vec<f64> = (0..n)
+        .flat_map(|i| columns.iter().map(move |c| c[i]))
+        .collect();
+    if points.iter().any(|v| !v.is_finite()) {
+        return Err("Selected coordinates are not all finite".into());
+    }
+    let point = |i: usize| &points[i * dim..(i + 1) * dim];
+    let distance = |i: usize, j: usize| -> f64 {
+        point(i)
+            .iter()
+            .zip(point(j))
+            .map(|(a, b)| (a - b) * (a - b))
+            .sum::<f64>()
+            .sqrt()
+    };
+    let probabilities = scenario_probabilities(filtration);
+
+    // first pick: the scenario with the least expected distance to all others
+    let (first, _) = (0..n)
+        .into_par_iter()
+        .map(|u| {
+            let cost: f64 = (0..n).map(|i| probabilities[i] * distance(i, u)).sum();
+            (u, cost)
+        })
+        .min_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)))
+        .unwrap();
+    // distance of every scenario to the nearest kept scenario so far
+    let mut nearest: Vec<f64> = (0..n).map(|i| distance(i, first)).collect();
+    let mut kept = vec![first];
+
+    // Keeping u lowers the cost by gain(u). Gains only shrink as scenarios are
+    // kept (submodularity), so stale gains are upper bounds and most
+    // candidates never need to be re-evaluated (lazy greedy).
+    let gain = |u: usize, nearest: &[f64]| -> f64 {
+        (0..n)
+            .map(|i| probabilities[i] * (nearest[i] - distance(i, u)).max(0.0))
+            .sum()
+    };
+    let mut candidates: BinaryHeap<(OrderedFloat<f64>, Reverse<usize>)> = (0..n)
+        .into_par_iter()
+        .filter(|u| *u != first)
+        .map(|u| (OrderedFloat(gain(u, &nearest)), Reverse(u)))
+        .collect::<Vec<_>>()
+        .into();
+    while kept.len() < keep {
+        let Some((_, Reverse(u))) = candidates.pop() else {
+            break;
+        };
+        let fresh = OrderedFloat(gain(u, &nearest));
+        if candidates.peek().is_some_and(|(top, _)| fresh < *top) {
+            candidates.push((fresh, Reverse(u)));
+            continue;
+        }
+        kept.push(u);
+        nearest.par_iter_mut().enumerate().for_each(|(i, d)| {
+            *d = d.min(distance(i, u));
+        });
+    }
+
+    let assignment: Vec<usize> = (0..n)
+        .into_par_iter()
+        .map(|i| {
+            (0..kept.len())
+                .min_by(|a, b| distance(i, kept[*a]).total_cmp(&distance(i, kept[*b])))
+                .unwrap()
+        })
+        .collect();
+    let mut kept_probabilities = vec![0.0; kept.len()];
+    for (i, k) in assignment.iter().enumerate() {
+        kept_probabilities[*k] += probabilities[i];
+    }
+    Reduction::new(filtration, kept, kept_probabilities, assignment)
+}
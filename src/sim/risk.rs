@@ -0,0 +1,222 @@
+use crate::filtration::Filtration;
+use ordered_float::OrderedFloat;
+use polars::prelude::*;
+use std::collections::HashMap;
+
+/// Quantity the risk measures are computed on, evaluated per scenario and time.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Exposure {
+    Process(String),
+    /// Weighted sum of processes, e.g. a 60/40 portfolio
+    /// `{"A": 0.6, "B": 0.4}`.
+    Combination(HashMap<String, f64>),
+}
+
+impl From<&str> for Exposure {
+    fn from(process: &str) -> Self {
+        Exposure::Process(process.to_string())
+    }
+}
+
+impl From<HashMap<String, f64>> for Exposure {
+    fn from(combination: HashMap<String, f64>) -> Self {
+        Exposure::Combination(combination)
+    }
+}
+
+/// Finite exposures at one time with their probabilities, sorted ascending,
+/// and the number of scenarios excluded for non-finite values.
+struct Distribution {
+    values: Vec<f64>,
+    probabilities: Vec<f64>,
+    excluded: usize,
+}
+
+impl Distribution {
+    fn at(filtration: &Filtration, terms: &[(usize, f64)], t_idx: usize) -> Self {
+        let mut points: Vec<(f64, f64)> = (0..filtration.num_scenarios())
+            .map(|s_idx| {
+                let value: f64 = terms
+                    .iter()
+                    .map(|(p_idx, w)| w * filtration.get(s_idx, t_idx, *p_idx))
+                    .sum();
+                let weight = filtration.weights.as_ref().map_or(1.0, |w| w[s_idx]);
+                (value, weight)
+            })
+            .filter(|(value, weight)| value.is_finite() && weight.is_finite())
+            .collect();
+        let excluded = filtration.num_scenarios() - points.len();
+        points.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let total: f64 = points.iter().map(|(_, w)| w).sum();
+        let (values, probabilities) = points.into_iter().map(|(v, w)| (v, w / total)).unzip();
+        Self {
+            values,
+            probabilities,
+            excluded,
+        }
+    }
+
+    /// Smallest value whose cumulative probability reaches `level`.
+    fn quantile(&self, level: f64) -> f64 {
+        let mut cumulative = 0.0;
+        for (v, p) in self.values.iter().zip(&self.probabilities) {
+            cumulative += p;
+            if cumulative >= level - 1e-12 {
+                return *v;
+            }
+        }
+        self.values.last().copied().unwrap_or(f64::NAN)
+    }
+
+    /// Mean of the lower `level` tail, counting the quantile atom only up to
+    /// the mass needed to fill the tail (Acerbi-Tasche).
+    fn tail_mean(&self, level: f64) -> f64 {
+        let q = self.quantile(level);
+        let mut mass = 0.0;
+        let mut sum = 0.0;
+        for (v, p) in self.values.iter().zip(&self.probabilities) {
+            if *v >= q {
+                break;
+            }
+            mass += p;
+            sum += p * v;
+        }
+        (sum + q * (level - mass)) / level
+    }
+
+    fn probability_below(&self, threshold: f64) -> f64 {
+        let below = self.values.partition_point(|v| *v < threshold);
+        if self.values.is_empty() {
+            f64::NAN
+        } else {
+            self.probabilities[..below].iter().sum()
+        }
+    }
+}
+
+fn resolve(filtration: &Filtration, exposure: &Exposure) -> PolarsResult<Vec<(usize, f64)>> {
+    let process_idx = |name: &str| match filtration.get_process_idx(name) {
+        Some(idx) => Ok(*idx),
+        None => polars_bail!(ComputeError: "unknown process '{}'", name),
+    };
+    match exposure {
+        Exposure::Process(name) => Ok(vec![(process_idx(name)?, 1.0)]),
+        Exposure::Combination(weights) => {
+            if weights.is_empty() {
+                polars_bail!(ComputeError: "a combination needs at least one process");
+            }
+            let mut terms: Vec<(usize, f64)> = weights
+                .iter()
+                .map(|(name, w)| Ok((process_idx(name)?, *w)))
+                .collect::<PolarsResult<_>>()?;
+            // fixed summation order, so results do not depend on hashing
+            terms.sort_by_key(|(p_idx, _)| *p_idx);
+            Ok(terms)
+        }
+    }
+}
+
+fn distributions(
+    filtration: &Filtration,
+    exposure: &Exposure,
+    times: &[f64],
+) -> PolarsResult<Vec<Distribution>> {
+    let terms = resolve(filtration, exposure)?;
+    times
+        .iter()
+        .map(|&t| match filtration.get_time_idx(OrderedFloat(t)) {
+            Some(&t_idx) => Ok(Distribution::at(filtration, &terms, t_idx)),
+            None => polars_bail!(ComputeError: "time {} is not part of the filtration", t),
+        })
+        .collect()
+}
+
+fn check_alpha(alpha: f64) -> PolarsResult<()> {
+    if !(alpha > 0.0 && alpha < 1.0) {
+        polars_bail!(ComputeError: "alpha must lie in (0, 1), got {}", alpha);
+    }
+    Ok(())
+}
+
+/// Value-at-Risk at confidence `alpha` (e.g. 0.99) of `exposure` read as a
+/// profit, i.e. minus its `1 - alpha` quantile, at each of `times`. Columns
+/// are `time`, `alpha`, `var`, `quantile` and `excluded`, the number of
+/// scenarios skipped for a non-finite exposure. Scenarios are weighted by
+/// their normalized likelihood ratios under importance sampling.
+pub fn var(
+    filtration: &Filtration,
+    exposure: &Exposure,
+    alpha: f64,
+    times: &[f64],
+) -> PolarsResult<DataFrame> {
+    check_alpha(alpha)?;
+    let dists = distributions(filtration, exposure, times)?;
+    let quantiles: Vec<f64> = dists.iter().map(|d| d.quantile(1.0 - alpha)).collect();
+    DataFrame::new(vec![
+        Column::new("time".into(), times.to_vec()),
+        Column::new("alpha".into(), vec![alpha; times.len()]),
+        Column::new(
+            "var".into(),
+            quantiles.iter().map(|q| -q).collect::<Vec<_>>(),
+        ),
+        Column::new("quantile".into(), quantiles),
+        Column::new("excluded".into(), excluded(&dists)),
+    ])
+}
+
+/// Conditional Value-at-Risk (expected shortfall) at confidence `alpha`:
+/// minus the mean of the worst `1 - alpha` of outcomes, with columns `time`,
+/// `alpha`, `var`, `cvar` and `excluded`. See [`var`].
+pub fn cvar(
+    filtration: &Filtration,
+    exposure: &Exposure,
+    alpha: f64,
+    times: &[f64],
+) -> PolarsResult<DataFrame> {
+    check_alpha(alpha)?;
+    let dists = distributions(filtration, exposure, times)?;
+    let level = 1.0 - alpha;
+    DataFrame::new(vec![
+        Column::new("time".into(), times.to_vec()),
+        Column::new("alpha".into(), vec![alpha; times.len()]),
+        Column::new(
+            "var".into(),
+            dists.iter().map(|d| -d.quantile(level)).collect::<Vec<_>>(),
+        ),
+        Column::new(
+            "cvar".into(),
+            dists
+                .iter()
+                .map(|d| -d.tail_mean(level))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new("excluded".into(), excluded(&dists)),
+    ])
+}
+
+/// Probability that `exposure` lies strictly below `threshold`, with columns
+/// `time`, `threshold`, `probability` and `excluded`. See [`var`].
+pub fn shortfall_probability(
+    filtration: &Filtration,
+    exposure: &Exposure,
+    threshold: f64,
+    times: &[f64],
+) -> PolarsResult<DataFrame> {
+    let dists = distributions(filtration, exposure, times)?;
+    DataFrame::new(vec![
+        Column::new("time".into(), times.to_vec()),
+        Column::new("threshold".into(), vec![threshold; times.len()]),
+        Column::new(
+            "probability".into(),
+            dists
+                .iter()
+                .map(|d| d.probability_below(threshold))
+                .collect::<Vec<_>>(),
+        ),
+        Column::new("excluded".into(), excluded(&dists)),
+    ])
+}
+
+fn excluded(dists: &[Distribution]) -> Vec<u64> {
+    dists.iter().map(|d| d.excluded as u64).collect()
+}
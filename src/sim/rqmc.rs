@@ -0,0 +1,46 @@
+use crate::filtration::Filtration;
+use crate::math::stats;
+
+/// Estimates `statistic` of `process` at the final time by computing it on every
+/// replication separately, returning the mean over replications and its
+/// standard error.
+///
+/// `replication_assignment` gives the replication of every scenario, usually
+/// `filtration.replications` of a run configured with `with_replications`.
+pub fn rqmc_error(
+    filtration: &Filtration,
+    process: &str,
+    statistic: impl Fn(&[f64]) -> f64,
+    replication_assignment: &[usize],
+) -> Result<(f64, f64), String> {
+    if replication_assignment.len() != filtration.num_scenarios() {
+        return Err(format!(
+            "Replication assignment has {} entries but the filtration holds {} scenarios",
+            replication_assignment.len(),
+            filtration.num_scenarios()
+        ));
+    }
+    let final_time = filtration
+        .times
+        .last()
+        .ok_or("Filtration has no time steps")?
+        .0;
+    let values = filtration.process_values(final_time, process)?;
+
+    let num_replications = replication_assignment.iter().max().map_or(0, |r| r + 1);
+    let mut groups: Vec<Vec<f64>> = vec![Vec::new(); num_replications];
+    for (val, &r) in values.iter().zip(replication_assignment.iter()) {
+        groups[r].push(*val);
+    }
+    let estimates: Vec<f64> = groups
+        .iter()
+        .filter(|g| !g.is_empty())
+        .map(|g| statistic(g))
+        .collect();
+    if estimates.len() < 2 {
+        return Err("At least two non-empty replications are required".into());
+    }
+
+    let std_error = stats::std_dev(&estimates) / (estimates.len() as f64).sqrt();
+    Ok((stats::mean(&estimates), std_error))
+}
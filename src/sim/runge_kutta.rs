@@ -14,8 +14,10 @@ pub fn runge_kutta_iteration(
     let dt = (next_time - current_time).into_inner();
     let sqrt_dt = dt.sqrt();
 
-    // 1. Generate the sk random variable (±1) for the stochastic correction
-    let sk = if rng.sample(t_idx, 0) > 0.5 {
+    // 1. Generate the sk random variable (±1) for the stochastic correction.
+    // It is drawn from its own auxiliary dimension, right after the stochastic
+    // drivers, so that it is independent of every increment.
+    let sk = if rng.sample(t_idx, process_universe.stochastic_registry.len()) > 0.5 {
         1.0
     } else {
         -1.0
@@ -28,7 +30,12 @@ pub fn runge_kutta_iteration(
         let mut incs = Vec::new();
         if let Process::Levy(levy) = &process_universe.processes[p_idx] {
             for incr in &levy.incrementors {
-                incs.push(incr.sample(t_idx, filtration, rng));
+                let mut x = incr.sample(t_idx, filtration, rng);
+                if let Some(idx) = incr.stochastic_idx() {
+                    x = filtration.shift_increment(idx, x);
+                    filtration.record_increment(t_idx, idx, x);
+                }
+                incs.push(x);
             }
         }
         step_increments.push(incs);
@@ -63,15 +70,9 @@ pub fn runge_kutta_iteration(
         if let Process::Levy(levy) = &process_universe.processes[p_idx] {
             // Find the diffusion perturbation (only if dW exists in this process)
             let mut perturbation = 0.0;
-            for (inc_idx, incr) in levy.incrementors.iter().enumerate() {
-                if incr.is_wiener() {
-                    // This is the core of the Stochastic RK Strong Order 1.0 logic
-                    perturbation += levy.coefficients[inc_idx]
-                        .eval(current_time, filtration)
-                        .unwrap()
-                        * sk
-                        * sqrt_dt;
-                }
+            for (coefficient, _) in levy.diffusion_terms() {
+                // This is the core of the Stochastic RK Strong Order 1.0 logic
+                perturbation += coefficient.eval(current_time, filtration).unwrap() * sk * sqrt_dt;
             }
             filtration.set(t_idx + 1, p_idx, x_t[p_idx] + k1[p_idx] + perturbation);
         }